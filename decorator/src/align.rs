@@ -32,6 +32,21 @@ impl Alignment {
     pub fn centre() -> Self {
         Self::new(AlignmentX::Centre, AlignmentY::Centre)
     }
+    /// The offset at which to place something of size `inner` within
+    /// something of size `outer`
+    pub fn offset(self, outer: Size, inner: Size) -> Coord {
+        let x = match self.x {
+            AlignmentX::Left => 0,
+            AlignmentX::Centre => (outer.x() as i32 - inner.x() as i32) / 2,
+            AlignmentX::Right => outer.x() as i32 - inner.x() as i32,
+        };
+        let y = match self.y {
+            AlignmentY::Top => 0,
+            AlignmentY::Centre => (outer.y() as i32 - inner.y() as i32) / 2,
+            AlignmentY::Bottom => outer.y() as i32 - inner.y() as i32,
+        };
+        Coord::new(x, y)
+    }
 }
 
 pub struct AlignView<V> {
@@ -46,20 +61,7 @@ where
 {
     fn view<F: Frame, C: ColModify>(&mut self, data: T, context: ViewContext<C>, frame: &mut F) {
         let data_size = self.view.size(data.clone(), context);
-        let x_offset = match self.alignment.x {
-            AlignmentX::Left => 0,
-            AlignmentX::Centre => (context.size.x() as i32 - data_size.x() as i32) / 2,
-            AlignmentX::Right => context.size.x() as i32 - data_size.x() as i32,
-        };
-        let y_offset = match self.alignment.y {
-            AlignmentY::Top => 0,
-            AlignmentY::Centre => (context.size.y() as i32 - data_size.y() as i32) / 2,
-            AlignmentY::Bottom => context.size.y() as i32 - data_size.y() as i32,
-        };
-        self.view.view(
-            data,
-            context.add_offset(Coord::new(x_offset, y_offset)),
-            frame,
-        );
+        let offset = self.alignment.offset(context.size, data_size);
+        self.view.view(data, context.add_offset(offset), frame);
     }
 }
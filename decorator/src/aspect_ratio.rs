@@ -0,0 +1,62 @@
+use chargrid_render::*;
+
+/// Give a view the largest space with the proportions of `aspect_ratio`
+/// (measured in cells) that fits within the space available, starting at the
+/// top-left corner. Wrap it in an `AlignView` to position it elsewhere.
+pub struct AspectRatioView<V> {
+    pub view: V,
+    pub aspect_ratio: Size,
+}
+
+fn fit_aspect_ratio(available: Size, aspect_ratio: Size) -> Size {
+    if aspect_ratio.width() == 0 || aspect_ratio.height() == 0 {
+        return Size::new_u16(0, 0);
+    }
+    let width_from_height =
+        (available.height() as u64 * aspect_ratio.width() as u64) / aspect_ratio.height() as u64;
+    if width_from_height <= available.width() as u64 {
+        Size::new(width_from_height as u32, available.height())
+    } else {
+        let height_from_width =
+            (available.width() as u64 * aspect_ratio.height() as u64) / aspect_ratio.width() as u64;
+        Size::new(available.width(), height_from_width as u32)
+    }
+}
+
+impl<V, T> View<T> for AspectRatioView<V>
+where
+    V: View<T>,
+{
+    fn view<F: Frame, C: ColModify>(&mut self, data: T, context: ViewContext<C>, frame: &mut F) {
+        let size = fit_aspect_ratio(context.size, self.aspect_ratio);
+        self.view.view(data, context.constrain_size_to(size), frame);
+    }
+
+    fn size<C: ColModify>(&mut self, _data: T, context: ViewContext<C>) -> Size {
+        fit_aspect_ratio(context.size, self.aspect_ratio)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_view::*;
+    use crate::{AlignView, Alignment};
+
+    #[test]
+    fn centred_square() {
+        let mut buffer = Buffer::new(Size::new(100, 10));
+        let context = ViewContext::default_with_size(buffer.size());
+        let mut view = AlignView {
+            view: AspectRatioView {
+                view: Fill::new(200, 200, '#'),
+                aspect_ratio: Size::new(1, 1),
+            },
+            alignment: Alignment::centre(),
+        };
+        assert_eq!(view.view.size((), context), Size::new(10, 10));
+        view.view((), context, &mut buffer);
+        let expected = format!("{}{}{}", " ".repeat(45), "#".repeat(10), " ".repeat(45));
+        assert!(rows(&buffer).iter().all(|row| *row == expected));
+    }
+}
@@ -0,0 +1,73 @@
+use chargrid_render::*;
+
+/// Render `backdrop` with `col_modify` applied, and then render `view` on top
+/// of it. Typically `backdrop` is the screen which a dialog is drawn over, and
+/// `col_modify` dims it so the dialog stands out. The backdrop covers the
+/// whole view context, so that is the size of the view.
+pub struct BackdropView<B, V, C> {
+    pub backdrop: B,
+    pub view: V,
+    pub col_modify: C,
+}
+
+impl<B, V, C, T> View<T> for BackdropView<B, V, C>
+where
+    B: View<T>,
+    V: View<T>,
+    C: ColModify,
+    T: Clone,
+{
+    fn view<F: Frame, C1: ColModify>(&mut self, data: T, context: ViewContext<C1>, frame: &mut F) {
+        self.backdrop.view(
+            data.clone(),
+            context.compose_col_modify(self.col_modify),
+            frame,
+        );
+        self.view.view(data, context.add_depth(1), frame);
+    }
+
+    fn size<C1: ColModify>(&mut self, _data: T, context: ViewContext<C1>) -> Size {
+        context.size
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_view::*;
+    use crate::{AlignView, Alignment};
+
+    #[test]
+    fn backdrop_is_dimmed_behind_view() {
+        let mut buffer = Buffer::new(Size::new(4, 2));
+        let context = ViewContext::default_with_size(buffer.size());
+        let dim = Rgb24::new_grey(100);
+        let mut view = BackdropView {
+            backdrop: Fill::new(4, 2, '.'),
+            view: Fill::new(2, 1, '#'),
+            col_modify: ColModifyMap(|_| dim),
+        };
+        assert_eq!(view.size((), context), Size::new(4, 2));
+        view.view((), context, &mut buffer);
+        assert_eq!(rows(&buffer), &["##..", "...."]);
+        assert_eq!(background(&buffer, 0, 0), WHITE);
+        assert_eq!(background(&buffer, 2, 0), dim);
+        assert_eq!(background(&buffer, 3, 1), dim);
+    }
+
+    #[test]
+    fn aligned_backdrop_covers_context() {
+        let mut buffer = Buffer::new(Size::new(4, 2));
+        let context = ViewContext::default_with_size(buffer.size());
+        AlignView {
+            view: BackdropView {
+                backdrop: Fill::new(4, 2, '.'),
+                view: Fill::new(2, 1, '#'),
+                col_modify: ColModifyMap(|_| Rgb24::new_grey(100)),
+            },
+            alignment: Alignment::centre(),
+        }
+        .view((), context, &mut buffer);
+        assert_eq!(rows(&buffer), &["##..", "...."]);
+    }
+}
@@ -1,17 +1,35 @@
 mod align;
+mod aspect_ratio;
+mod backdrop;
 mod border;
 mod bound;
 mod col_modify;
 mod fill_background;
+mod margin;
+mod max_size;
 mod min_size;
 mod pad;
+mod shadow;
+mod split;
+mod tabs;
+#[cfg(test)]
+mod test_view;
+mod transition;
 mod vertical_scroll;
 
 pub use align::*;
+pub use aspect_ratio::*;
+pub use backdrop::*;
 pub use border::*;
 pub use bound::*;
 pub use col_modify::*;
 pub use fill_background::*;
+pub use margin::*;
+pub use max_size::*;
 pub use min_size::*;
 pub use pad::*;
+pub use shadow::*;
+pub use split::*;
+pub use tabs::*;
+pub use transition::*;
 pub use vertical_scroll::*;
@@ -0,0 +1,159 @@
+use chargrid_render::*;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Margin {
+    /// A fixed number of cells
+    Absolute(u32),
+    /// A percentage of the width (for left and right margins) or height
+    /// (for top and bottom margins) of the space available
+    Percent(u8),
+}
+
+impl Default for Margin {
+    fn default() -> Self {
+        Margin::Absolute(0)
+    }
+}
+
+impl Margin {
+    /// The number of cells in the margin, which is at most `i32::MAX` so it
+    /// can be used as a coordinate
+    fn cells(self, total: u32) -> u32 {
+        match self {
+            Margin::Absolute(cells) => cells.min(i32::MAX as u32),
+            Margin::Percent(percent) => (total as u64 * percent.min(100) as u64 / 100) as u32,
+        }
+    }
+}
+
+fn saturating_add(a: Size, b: Size) -> Size {
+    Size::new(
+        a.width().saturating_add(b.width()).min(Size::max_field()),
+        a.height().saturating_add(b.height()).min(Size::max_field()),
+    )
+}
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Default, Debug, Clone, Copy)]
+pub struct Margins {
+    pub top: Margin,
+    pub bottom: Margin,
+    pub left: Margin,
+    pub right: Margin,
+}
+
+impl Margins {
+    pub fn all(margin: Margin) -> Self {
+        Self {
+            top: margin,
+            bottom: margin,
+            left: margin,
+            right: margin,
+        }
+    }
+    fn top_left(self, available: Size) -> Coord {
+        Coord::new(
+            self.left.cells(available.width()) as i32,
+            self.top.cells(available.height()) as i32,
+        )
+    }
+    fn bottom_right(self, available: Size) -> Size {
+        Size::new(
+            self.right.cells(available.width()),
+            self.bottom.cells(available.height()),
+        )
+    }
+    fn total(self, available: Size) -> Size {
+        let top_left = Size::new(
+            self.left.cells(available.width()),
+            self.top.cells(available.height()),
+        );
+        saturating_add(top_left, self.bottom_right(available))
+    }
+}
+
+/// Surround a view with empty space. Percentage margins are relative to the
+/// space available to the `MarginView`.
+pub struct MarginView<V> {
+    pub view: V,
+    pub margins: Margins,
+}
+
+impl<V> MarginView<V> {
+    fn child_context<C: ColModify>(&self, context: ViewContext<C>) -> ViewContext<C> {
+        context
+            .add_offset(self.margins.top_left(context.size))
+            .constrain_size_by(self.margins.bottom_right(context.size))
+    }
+}
+
+impl<V, T> View<T> for MarginView<V>
+where
+    V: View<T>,
+{
+    fn view<F: Frame, C: ColModify>(&mut self, data: T, context: ViewContext<C>, frame: &mut F) {
+        let child_context = self.child_context(context);
+        self.view.view(data, child_context, frame);
+    }
+
+    fn size<C: ColModify>(&mut self, data: T, context: ViewContext<C>) -> Size {
+        let child_context = self.child_context(context);
+        saturating_add(
+            self.view.size(data, child_context),
+            self.margins.total(context.size),
+        )
+    }
+
+    fn view_size<F: Frame, C: ColModify>(
+        &mut self,
+        data: T,
+        context: ViewContext<C>,
+        frame: &mut F,
+    ) -> Size {
+        let child_context = self.child_context(context);
+        saturating_add(
+            self.view.view_size(data, child_context, frame),
+            self.margins.total(context.size),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_view::*;
+
+    #[test]
+    fn percentage_and_absolute_margins() {
+        let mut buffer = Buffer::new(Size::new(10, 4));
+        let context = ViewContext::default_with_size(buffer.size());
+        let mut view = MarginView {
+            view: Fill::new(100, 100, '#'),
+            margins: Margins {
+                top: Margin::Absolute(1),
+                bottom: Margin::Percent(25),
+                left: Margin::Percent(20),
+                right: Margin::Absolute(3),
+            },
+        };
+        assert_eq!(view.size((), context), Size::new(10, 4));
+        view.view((), context, &mut buffer);
+        assert_eq!(
+            rows(&buffer),
+            &["          ", "  #####   ", "  #####   ", "          "]
+        );
+    }
+
+    #[test]
+    fn huge_margins_saturate() {
+        let context = ViewContext::default_with_size(Size::new(10, 4));
+        let mut view = MarginView {
+            view: Fill::new(1, 1, '#'),
+            margins: Margins::all(Margin::Absolute(u32::MAX)),
+        };
+        assert_eq!(view.size((), context), Size::max());
+    }
+}
@@ -0,0 +1,55 @@
+use crate::Alignment;
+use chargrid_render::*;
+
+/// Limit the space available to a view to at most `size`, starting at the
+/// top-left corner. The view is placed within that space according to
+/// `alignment`. Wrap it in an `AlignView` to position the space itself.
+pub struct MaxSizeView<V> {
+    pub view: V,
+    pub size: Size,
+    pub alignment: Alignment,
+}
+
+impl<V, T> View<T> for MaxSizeView<V>
+where
+    V: View<T>,
+    T: Clone,
+{
+    fn view<F: Frame, C: ColModify>(&mut self, data: T, context: ViewContext<C>, frame: &mut F) {
+        let context = context.constrain_size_to(self.size);
+        let view_size = self.view.size(data.clone(), context);
+        let offset = self.alignment.offset(context.size, view_size);
+        self.view.view(data, context.add_offset(offset), frame);
+    }
+
+    fn size<C: ColModify>(&mut self, _data: T, context: ViewContext<C>) -> Size {
+        context.size.pairwise_min(self.size)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_view::*;
+    use crate::{AlignView, AlignmentX, AlignmentY};
+
+    #[test]
+    fn aligned_within_aligned_box() {
+        let mut buffer = Buffer::new(Size::new(10, 4));
+        let context = ViewContext::default_with_size(buffer.size());
+        let mut view = AlignView {
+            view: MaxSizeView {
+                view: Fill::new(2, 1, '#'),
+                size: Size::new(6, 2),
+                alignment: Alignment::new(AlignmentX::Right, AlignmentY::Bottom),
+            },
+            alignment: Alignment::centre(),
+        };
+        assert_eq!(view.view.size((), context), Size::new(6, 2));
+        view.view((), context, &mut buffer);
+        assert_eq!(
+            rows(&buffer),
+            &["          ", "          ", "      ##  ", "          "]
+        );
+    }
+}
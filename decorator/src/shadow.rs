@@ -0,0 +1,92 @@
+use chargrid_render::*;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+/// The appearance of a drop shadow. The shadow is a copy of the decorated
+/// view's bounding rectangle, shifted by `offset`, whose background is blended
+/// towards `rgb24` by `alpha`.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy)]
+pub struct ShadowStyle {
+    pub offset: Coord,
+    pub rgb24: Rgb24,
+    pub alpha: u8,
+}
+
+impl Default for ShadowStyle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShadowStyle {
+    pub fn new() -> Self {
+        Self {
+            offset: Coord::new(1, 1),
+            rgb24: Rgb24::new_grey(0),
+            alpha: 127,
+        }
+    }
+}
+
+/// Decorate another element with a drop shadow. The shadow darkens whatever
+/// has already been drawn beneath it, so the view behind a dialog should be
+/// rendered before the dialog itself. The shadow may extend beyond the space
+/// allocated to this view.
+pub struct ShadowView<V> {
+    pub view: V,
+    pub style: ShadowStyle,
+}
+
+impl<V, T> View<T> for ShadowView<V>
+where
+    V: View<T>,
+{
+    fn view<F: Frame, C: ColModify>(&mut self, data: T, context: ViewContext<C>, frame: &mut F) {
+        let size = self.view.view_size(data, context.add_depth(1), frame);
+        let rgb24 = match context.col_modify.background(Some(self.style.rgb24)) {
+            Some(rgb24) => rgb24,
+            None => return,
+        };
+        for y in 0..(size.height() as i32) {
+            for x in 0..(size.width() as i32) {
+                let coord = Coord::new(x, y) + self.style.offset;
+                if coord.is_valid(size) {
+                    continue;
+                }
+                frame.blend_cell_background_absolute(
+                    coord + context.offset,
+                    context.depth,
+                    rgb24,
+                    self.style.alpha,
+                    blend_mode::LinearInterpolate,
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_view::*;
+
+    #[test]
+    fn shadow_darkens_offset_cells() {
+        let mut buffer = Buffer::new(Size::new(4, 3));
+        let context = ViewContext::default_with_size(buffer.size());
+        Fill::new(4, 3, '.').view((), context, &mut buffer);
+        ShadowView {
+            view: Fill::new(2, 1, '#'),
+            style: ShadowStyle::new(),
+        }
+        .view((), context, &mut buffer);
+        assert_eq!(rows(&buffer), &["##..", "....", "...."]);
+        let shadow = WHITE.linear_interpolate(Rgb24::new_grey(0), 127);
+        assert_eq!(background(&buffer, 1, 1), shadow);
+        assert_eq!(background(&buffer, 2, 1), shadow);
+        assert_eq!(background(&buffer, 0, 1), WHITE);
+        assert_eq!(background(&buffer, 2, 0), WHITE);
+        assert_eq!(background(&buffer, 1, 0), WHITE);
+    }
+}
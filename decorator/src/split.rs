@@ -0,0 +1,383 @@
+use crate::MinSizeView;
+use chargrid_input::{Input, KeyboardInput, MouseButton, MouseInput};
+use chargrid_render::*;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+/// The direction in which the available space is divided between the two
+/// panes of a split view.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitAxis {
+    /// The first pane is left of the divider, and the second pane is right of it
+    Horizontal,
+    /// The first pane is above the divider, and the second pane is below it
+    Vertical,
+}
+
+impl SplitAxis {
+    fn get_size(self, size: Size) -> u32 {
+        match self {
+            SplitAxis::Horizontal => size.width(),
+            SplitAxis::Vertical => size.height(),
+        }
+    }
+    fn get_other_size(self, size: Size) -> u32 {
+        match self {
+            SplitAxis::Horizontal => size.height(),
+            SplitAxis::Vertical => size.width(),
+        }
+    }
+    fn get_coord(self, coord: Coord) -> i32 {
+        match self {
+            SplitAxis::Horizontal => coord.x,
+            SplitAxis::Vertical => coord.y,
+        }
+    }
+    fn new_size(self, this_axis: u32, other_axis: u32) -> Size {
+        match self {
+            SplitAxis::Horizontal => Size::new(this_axis, other_axis),
+            SplitAxis::Vertical => Size::new(other_axis, this_axis),
+        }
+    }
+    fn new_coord(self, this_axis: i32, other_axis: i32) -> Coord {
+        match self {
+            SplitAxis::Horizontal => Coord::new(this_axis, other_axis),
+            SplitAxis::Vertical => Coord::new(other_axis, this_axis),
+        }
+    }
+}
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy)]
+pub struct SplitStyle {
+    pub axis: SplitAxis,
+    pub divider: ViewCell,
+    pub divider_dragging: ViewCell,
+    /// The first pane is never made smaller than this along the split axis
+    pub first_min_size: u32,
+    /// The second pane is never made smaller than this along the split axis
+    pub second_min_size: u32,
+    /// The number of cells the divider moves by when resized with the keyboard
+    pub keyboard_step: u32,
+    /// The key which moves the divider towards the start of the split view
+    pub shrink_key: Option<KeyboardInput>,
+    /// The key which moves the divider towards the end of the split view
+    pub grow_key: Option<KeyboardInput>,
+}
+
+impl SplitStyle {
+    pub fn new(axis: SplitAxis) -> Self {
+        let character = match axis {
+            SplitAxis::Horizontal => '│',
+            SplitAxis::Vertical => '─',
+        };
+        let divider = ViewCell::new()
+            .with_character(character)
+            .with_foreground(Rgb24::new_grey(255));
+        Self {
+            axis,
+            divider,
+            divider_dragging: divider.with_bold(true),
+            first_min_size: 1,
+            second_min_size: 1,
+            keyboard_step: 1,
+            shrink_key: None,
+            grow_key: None,
+        }
+    }
+    /// Resize the split with the keyboard. There are no resize keys by
+    /// default, as they would be taken from text inputs in either pane.
+    pub fn with_resize_keys(self, shrink_key: KeyboardInput, grow_key: KeyboardInput) -> Self {
+        Self {
+            shrink_key: Some(shrink_key),
+            grow_key: Some(grow_key),
+            ..self
+        }
+    }
+    pub fn horizontal() -> Self {
+        Self::new(SplitAxis::Horizontal)
+    }
+    pub fn vertical() -> Self {
+        Self::new(SplitAxis::Vertical)
+    }
+}
+
+impl Default for SplitStyle {
+    fn default() -> Self {
+        Self::horizontal()
+    }
+}
+
+/// Where the divider is placed, measured from the start of the split view.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitPosition {
+    /// The first pane occupies this many cells
+    Cells(u32),
+    /// The first pane occupies this percentage of the split view
+    Percent(u8),
+}
+
+/// Information about the most recent rendering of a split view, needed to
+/// interpret mouse input.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy)]
+pub struct SplitLimits {
+    last_rendered_offset: Coord,
+    last_rendered_size: Size,
+}
+
+impl SplitLimits {
+    pub fn new() -> Self {
+        Self {
+            last_rendered_offset: Coord::new(0, 0),
+            last_rendered_size: Size::new_u16(0, 0),
+        }
+    }
+}
+
+impl Default for SplitLimits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy)]
+pub struct SplitState {
+    position: SplitPosition,
+    dragging: bool,
+}
+
+fn first_pane_size(position: SplitPosition, total: u32, style: &SplitStyle) -> u32 {
+    let available = total.saturating_sub(1);
+    let requested = match position {
+        SplitPosition::Cells(cells) => cells,
+        SplitPosition::Percent(percent) => (available * percent.min(100) as u32) / 100,
+    };
+    requested
+        .min(available.saturating_sub(style.second_min_size))
+        .max(style.first_min_size)
+        .min(available)
+}
+
+impl SplitState {
+    pub fn new(position: SplitPosition) -> Self {
+        Self {
+            position,
+            dragging: false,
+        }
+    }
+    pub fn position(self) -> SplitPosition {
+        self.position
+    }
+    pub fn is_dragging(self) -> bool {
+        self.dragging
+    }
+    pub fn set_position(&mut self, position: SplitPosition) {
+        self.position = position;
+    }
+    /// The size along the split axis of the first pane as of the most recent rendering
+    pub fn first_pane_size(self, style: &SplitStyle, limits: SplitLimits) -> u32 {
+        first_pane_size(
+            self.position,
+            style.axis.get_size(limits.last_rendered_size),
+            style,
+        )
+    }
+    pub fn move_divider(&mut self, delta: i32, style: &SplitStyle, limits: SplitLimits) {
+        let total = style.axis.get_size(limits.last_rendered_size);
+        let current = self.first_pane_size(style, limits) as i32;
+        let requested = (current + delta).max(0) as u32;
+        self.position = SplitPosition::Cells(first_pane_size(
+            SplitPosition::Cells(requested),
+            total,
+            style,
+        ));
+    }
+    fn divider_coord(self, style: &SplitStyle, limits: SplitLimits) -> i32 {
+        style.axis.get_coord(limits.last_rendered_offset)
+            + self.first_pane_size(style, limits) as i32
+    }
+    fn is_on_divider(self, coord: Coord, style: &SplitStyle, limits: SplitLimits) -> bool {
+        let rel_coord = coord - limits.last_rendered_offset;
+        rel_coord.is_valid(limits.last_rendered_size)
+            && style.axis.get_coord(coord) == self.divider_coord(style, limits)
+    }
+    /// Update the state in response to an input. Dragging the divider with
+    /// the left mouse button moves it, as do the style's resize keys. Returns
+    /// true if the input was consumed by the split view.
+    pub fn handle_input(&mut self, input: Input, style: &SplitStyle, limits: SplitLimits) -> bool {
+        let step = style.keyboard_step as i32;
+        match input {
+            Input::Keyboard(keyboard_input) if Some(keyboard_input) == style.shrink_key => {
+                self.move_divider(-step, style, limits);
+                true
+            }
+            Input::Keyboard(keyboard_input) if Some(keyboard_input) == style.grow_key => {
+                self.move_divider(step, style, limits);
+                true
+            }
+            Input::Mouse(MouseInput::MousePress {
+                button: MouseButton::Left,
+                coord,
+            }) => {
+                self.dragging = self.is_on_divider(coord, style, limits);
+                self.dragging
+            }
+            Input::Mouse(MouseInput::MouseMove { coord, .. }) => {
+                if self.dragging {
+                    let delta = style.axis.get_coord(coord) - self.divider_coord(style, limits);
+                    self.move_divider(delta, style, limits);
+                }
+                self.dragging
+            }
+            Input::Mouse(MouseInput::MouseRelease { .. }) => {
+                let was_dragging = self.dragging;
+                self.dragging = false;
+                was_dragging
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Default for SplitState {
+    fn default() -> Self {
+        Self::new(SplitPosition::Percent(50))
+    }
+}
+
+/// Divide the available space between two views, separated by a divider
+/// which can be moved by updating `state` with `SplitState::handle_input`.
+pub struct SplitView<'s, 'l, A, B> {
+    pub first: A,
+    pub second: B,
+    pub style: &'s SplitStyle,
+    pub limits: &'l mut SplitLimits,
+    pub state: SplitState,
+}
+
+impl<'s, 'l, A, B> SplitView<'s, 'l, A, B> {
+    /// The contexts of the first and second panes, and the size of the first
+    /// pane along the split axis
+    fn pane_contexts<C: ColModify>(
+        &self,
+        context: ViewContext<C>,
+    ) -> (ViewContext<C>, ViewContext<C>, u32) {
+        let axis = self.style.axis;
+        let total = axis.get_size(context.size);
+        let other = axis.get_other_size(context.size);
+        let first_size = first_pane_size(self.state.position, total, self.style);
+        let second_offset = first_size + 1;
+        let first_context = context.constrain_size_to(axis.new_size(first_size, other));
+        let second_context = context
+            .add_offset(axis.new_coord(second_offset as i32, 0))
+            .constrain_size_to(axis.new_size(total.saturating_sub(second_offset), other));
+        (first_context, second_context, first_size)
+    }
+}
+
+impl<'s, 'l, A, B, T> View<T> for SplitView<'s, 'l, A, B>
+where
+    A: View<T>,
+    B: View<T>,
+    T: Clone,
+{
+    fn view<F: Frame, C: ColModify>(&mut self, data: T, context: ViewContext<C>, frame: &mut F) {
+        let axis = self.style.axis;
+        let (first_context, second_context, first_size) = self.pane_contexts(context);
+        self.first.view(data.clone(), first_context, frame);
+        self.second.view(data, second_context, frame);
+        if axis.get_size(context.size) > first_size {
+            let divider = if self.state.dragging {
+                self.style.divider_dragging
+            } else {
+                self.style.divider
+            };
+            for i in 0..(axis.get_other_size(context.size) as i32) {
+                frame.set_cell_relative(axis.new_coord(first_size as i32, i), 1, divider, context);
+            }
+        }
+        self.limits.last_rendered_offset = context.offset;
+        self.limits.last_rendered_size = context.size;
+    }
+
+    /// Each pane is at least as large as its minimum size along the split
+    /// axis, in the manner of `MinSizeView`, and the split view fills the
+    /// space available to it.
+    fn size<C: ColModify>(&mut self, data: T, context: ViewContext<C>) -> Size {
+        let axis = self.style.axis;
+        let (first_context, second_context, _) = self.pane_contexts(context);
+        let first = MinSizeView {
+            view: &mut self.first,
+            size: axis.new_size(self.style.first_min_size, 0),
+        }
+        .size(data.clone(), first_context);
+        let second = MinSizeView {
+            view: &mut self.second,
+            size: axis.new_size(self.style.second_min_size, 0),
+        }
+        .size(data, second_context);
+        let min_size = axis.new_size(axis.get_size(first) + 1 + axis.get_size(second), 0);
+        context.size.pairwise_max(min_size)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_view::*;
+
+    fn render(style: &SplitStyle, limits: &mut SplitLimits, state: SplitState) -> Vec<String> {
+        let mut buffer = Buffer::new(Size::new(10, 2));
+        let context = ViewContext::default_with_size(buffer.size());
+        SplitView {
+            first: Fill::new(100, 100, 'a'),
+            second: Fill::new(100, 100, 'b'),
+            style,
+            limits,
+            state,
+        }
+        .view((), context, &mut buffer);
+        rows(&buffer)
+    }
+
+    #[test]
+    fn panes_respect_min_sizes() {
+        let style = SplitStyle {
+            first_min_size: 3,
+            second_min_size: 2,
+            ..SplitStyle::horizontal()
+        };
+        let mut limits = SplitLimits::new();
+        let mut render = |position| render(&style, &mut limits, SplitState::new(position));
+        assert_eq!(render(SplitPosition::Percent(50))[0], "aaaa│bbbbb");
+        assert_eq!(render(SplitPosition::Cells(0))[1], "aaa│bbbbbb");
+        assert_eq!(render(SplitPosition::Cells(9))[0], "aaaaaaa│bb");
+    }
+
+    #[test]
+    fn resize_with_keys_and_mouse() {
+        let style =
+            SplitStyle::horizontal().with_resize_keys(KeyboardInput::Left, KeyboardInput::Right);
+        let mut limits = SplitLimits::new();
+        let mut state = SplitState::new(SplitPosition::Cells(4));
+        render(&style, &mut limits, state);
+        assert!(!state.handle_input(Input::Keyboard(KeyboardInput::Char('<')), &style, limits));
+        assert!(state.handle_input(Input::Keyboard(KeyboardInput::Left), &style, limits));
+        assert_eq!(state.position(), SplitPosition::Cells(3));
+        let press = MouseInput::MousePress {
+            button: MouseButton::Left,
+            coord: Coord::new(3, 1),
+        };
+        assert!(state.handle_input(Input::Mouse(press), &style, limits));
+        let drag = MouseInput::MouseMove {
+            button: Some(MouseButton::Left),
+            coord: Coord::new(6, 0),
+        };
+        assert!(state.handle_input(Input::Mouse(drag), &style, limits));
+        assert_eq!(render(&style, &mut limits, state)[0], "aaaaaa│bbb");
+    }
+}
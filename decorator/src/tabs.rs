@@ -0,0 +1,328 @@
+use chargrid_input::{keys, Input, KeyboardInput, MouseInput};
+use chargrid_render::*;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy)]
+pub struct TabStyle {
+    pub active: Style,
+    pub inactive: Style,
+    /// Drawn between adjacent tab labels
+    pub separator: ViewCell,
+    /// Number of blank cells either side of each label
+    pub label_padding: u32,
+}
+
+impl TabStyle {
+    pub fn new() -> Self {
+        Self {
+            active: Style::new()
+                .with_foreground(Rgb24::new_grey(0))
+                .with_background(Rgb24::new_grey(255))
+                .with_bold(true),
+            inactive: Style::new()
+                .with_foreground(Rgb24::new_grey(255))
+                .with_bold(false),
+            separator: ViewCell::new()
+                .with_character('│')
+                .with_foreground(Rgb24::new_grey(127)),
+            label_padding: 1,
+        }
+    }
+}
+
+impl Default for TabStyle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TabAction {
+    Next,
+    Previous,
+    Select(usize),
+}
+
+/// Map from keys to the tab actions they perform. The default is empty, as
+/// keys bound here are never seen by the active tab's content. Use
+/// `TabKeyMap::standard` for Tab and the number keys 1 to 9.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serialize", serde(default))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TabKeyMap {
+    pub keyboard: HashMap<KeyboardInput, TabAction>,
+}
+
+impl TabKeyMap {
+    /// Tab moves to the next tab, and the number keys 1 to 9 select a tab
+    /// directly. Inputs don't carry modifier keys, so there is no binding for
+    /// moving to the previous tab.
+    pub fn standard() -> Self {
+        let mut keyboard: HashMap<_, _> = (0..9)
+            .map(|i| {
+                let c = (b'1' + i as u8) as char;
+                (KeyboardInput::Char(c), TabAction::Select(i))
+            })
+            .collect();
+        keyboard.insert(keys::TAB, TabAction::Next);
+        Self { keyboard }
+    }
+
+    pub fn with_key(mut self, keyboard_input: KeyboardInput, action: TabAction) -> Self {
+        self.bind_key(keyboard_input, action);
+        self
+    }
+
+    pub fn bind_key(&mut self, keyboard_input: KeyboardInput, action: TabAction) {
+        self.keyboard.insert(keyboard_input, action);
+    }
+
+    pub fn unbind_key(&mut self, keyboard_input: KeyboardInput) {
+        self.keyboard.remove(&keyboard_input);
+    }
+
+    pub fn action(&self, input: Input) -> Option<TabAction> {
+        match input {
+            Input::Keyboard(keyboard_input) => self.keyboard.get(&keyboard_input).cloned(),
+            _ => None,
+        }
+    }
+}
+
+/// The positions of tab labels as of the most recent rendering, needed to
+/// interpret mouse input.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default)]
+pub struct TabLimits {
+    last_rendered_offset: Coord,
+    label_x_ranges: Vec<(i32, i32)>,
+}
+
+impl TabLimits {
+    pub fn new() -> Self {
+        Self {
+            last_rendered_offset: Coord::new(0, 0),
+            label_x_ranges: Vec::new(),
+        }
+    }
+    pub fn num_tabs(&self) -> usize {
+        self.label_x_ranges.len()
+    }
+    pub fn tab_index_from_screen_coord(&self, coord: Coord) -> Option<usize> {
+        let rel_coord = coord - self.last_rendered_offset;
+        if rel_coord.y != 0 {
+            return None;
+        }
+        self.label_x_ranges
+            .iter()
+            .position(|&(start, end)| rel_coord.x >= start && rel_coord.x < end)
+    }
+}
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TabState {
+    index: usize,
+}
+
+impl TabState {
+    pub fn new(index: usize) -> Self {
+        Self { index }
+    }
+    pub fn index(self) -> usize {
+        self.index
+    }
+    pub fn set_index(&mut self, index: usize, num_tabs: usize) {
+        if index < num_tabs {
+            self.index = index;
+        }
+    }
+    pub fn next(&mut self, num_tabs: usize) {
+        if num_tabs > 0 {
+            self.index = (self.index + 1) % num_tabs;
+        }
+    }
+    pub fn previous(&mut self, num_tabs: usize) {
+        if num_tabs > 0 {
+            self.index = (self.index + num_tabs - 1) % num_tabs;
+        }
+    }
+    /// Update the state in response to an input. Keys in `key_map` perform
+    /// their actions, and clicking a label selects its tab. Returns true if
+    /// the input was consumed.
+    pub fn handle_input(
+        &mut self,
+        input: Input,
+        key_map: &TabKeyMap,
+        num_tabs: usize,
+        limits: &TabLimits,
+    ) -> bool {
+        if let Some(action) = key_map.action(input) {
+            match action {
+                TabAction::Next => self.next(num_tabs),
+                TabAction::Previous => self.previous(num_tabs),
+                TabAction::Select(index) => {
+                    if index >= num_tabs {
+                        return false;
+                    }
+                    self.index = index;
+                }
+            }
+            return true;
+        }
+        match input {
+            Input::Mouse(MouseInput::MousePress { coord, .. }) => {
+                match limits.tab_index_from_screen_coord(coord) {
+                    Some(index) if index < num_tabs => {
+                        self.index = index;
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+}
+
+pub struct Tab<V> {
+    pub label: String,
+    pub view: V,
+}
+
+impl<V> Tab<V> {
+    pub fn new<S: Into<String>>(label: S, view: V) -> Self {
+        Self {
+            label: label.into(),
+            view,
+        }
+    }
+}
+
+/// Render a strip of tab labels, with the view of the active tab beneath it.
+/// Labels which don't fit in the width of the view are cut off.
+pub struct TabView<'s, 'l, 't, V> {
+    pub tabs: &'t mut [Tab<V>],
+    pub style: &'s TabStyle,
+    pub limits: &'l mut TabLimits,
+    pub state: TabState,
+}
+
+impl<'s, 'l, 't, V> TabView<'s, 'l, 't, V> {
+    pub fn active_view(&mut self) -> Option<&mut V> {
+        self.tabs.get_mut(self.state.index).map(|tab| &mut tab.view)
+    }
+}
+
+impl<'s, 'l, 't, V, T> View<T> for TabView<'s, 'l, 't, V>
+where
+    V: View<T>,
+{
+    fn view<F: Frame, C: ColModify>(&mut self, data: T, context: ViewContext<C>, frame: &mut F) {
+        self.limits.last_rendered_offset = context.offset;
+        self.limits.label_x_ranges.clear();
+        let padding = self.style.label_padding as i32;
+        let width = context.size.width() as i32;
+        let mut x = 0;
+        for (i, tab) in self.tabs.iter().enumerate() {
+            if x >= width {
+                self.limits.label_x_ranges.push((width, width));
+                continue;
+            }
+            if i > 0 {
+                frame.set_cell_relative(Coord::new(x, 0), 0, self.style.separator, context);
+                x += 1;
+            }
+            let style = if i == self.state.index {
+                self.style.active
+            } else {
+                self.style.inactive
+            };
+            let start = x;
+            let characters = (0..padding)
+                .map(|_| ' ')
+                .chain(tab.label.chars())
+                .chain((0..padding).map(|_| ' '));
+            for character in characters {
+                let view_cell = ViewCell {
+                    character: Some(character),
+                    style,
+                };
+                frame.set_cell_relative(Coord::new(x, 0), 0, view_cell, context);
+                x += 1;
+            }
+            self.limits
+                .label_x_ranges
+                .push((start.min(width), x.min(width)));
+        }
+        if let Some(view) = self.active_view() {
+            view.view(data, context.add_offset(Coord::new(0, 1)), frame);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_view::*;
+
+    #[test]
+    fn tab_keys_are_opt_in() {
+        let limits = TabLimits::new();
+        let mut state = TabState::default();
+        let digit = Input::Keyboard(KeyboardInput::Char('3'));
+        assert!(!state.handle_input(digit, &TabKeyMap::default(), 3, &limits));
+        assert!(!state.handle_input(
+            Input::Keyboard(keys::TAB),
+            &TabKeyMap::default(),
+            3,
+            &limits
+        ));
+        assert!(state.handle_input(digit, &TabKeyMap::standard(), 3, &limits));
+        assert_eq!(state.index(), 2);
+        assert!(state.handle_input(
+            Input::Keyboard(keys::TAB),
+            &TabKeyMap::standard(),
+            3,
+            &limits
+        ));
+        assert_eq!(state.index(), 0);
+        state.set_index(1, 3);
+        assert_eq!(state.index(), 1);
+    }
+
+    #[test]
+    fn tab_strip_is_clipped() {
+        let mut buffer = Buffer::new(Size::new(10, 2));
+        let context = ViewContext::default_with_size(Size::new(8, 2));
+        let mut tabs = vec![
+            Tab::new("map", Fill::new(100, 100, 'm')),
+            Tab::new("items", Fill::new(100, 100, 'i')),
+            Tab::new("spells", Fill::new(100, 100, 's')),
+        ];
+        let mut limits = TabLimits::new();
+        let mut state = TabState::new(1);
+        TabView {
+            tabs: &mut tabs,
+            style: &TabStyle::new(),
+            limits: &mut limits,
+            state,
+        }
+        .view((), context, &mut buffer);
+        assert_eq!(rows(&buffer), &[" map │ i  ", "iiiiiiii  "]);
+        let click = |x| {
+            Input::Mouse(MouseInput::MousePress {
+                button: chargrid_input::MouseButton::Left,
+                coord: Coord::new(x, 0),
+            })
+        };
+        let key_map = TabKeyMap::default();
+        assert!(!state.handle_input(click(9), &key_map, 3, &limits));
+        assert!(state.handle_input(click(2), &key_map, 3, &limits));
+        assert_eq!(state.index(), 0);
+    }
+}
@@ -0,0 +1,52 @@
+use chargrid_render::*;
+
+pub const WHITE: Rgb24 = Rgb24::new_grey(255);
+
+/// Fills a rectangle of `size` at the top-left of its context, clipped to
+/// the context, with a character on a white background
+pub struct Fill {
+    pub size: Size,
+    pub character: char,
+}
+
+impl Fill {
+    pub fn new(width: u32, height: u32, character: char) -> Self {
+        Self {
+            size: Size::new(width, height),
+            character,
+        }
+    }
+}
+
+impl<T> View<T> for Fill {
+    fn view<F: Frame, C: ColModify>(&mut self, _data: T, context: ViewContext<C>, frame: &mut F) {
+        let size = self.size.pairwise_min(context.size);
+        for y in 0..(size.height() as i32) {
+            for x in 0..(size.width() as i32) {
+                frame.set_cell_relative(
+                    Coord::new(x, y),
+                    0,
+                    ViewCell::new()
+                        .with_character(self.character)
+                        .with_background(WHITE),
+                    context,
+                );
+            }
+        }
+    }
+}
+
+pub fn rows(buffer: &Buffer) -> Vec<String> {
+    buffer
+        .rows()
+        .map(|row| row.iter().map(|cell| cell.character).collect())
+        .collect()
+}
+
+pub fn background(buffer: &Buffer, x: i32, y: i32) -> Rgb24 {
+    buffer
+        .enumerate()
+        .find(|&(coord, _)| coord == Coord::new(x, y))
+        .map(|(_, cell)| cell.background_colour)
+        .unwrap()
+}
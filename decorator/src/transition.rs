@@ -0,0 +1,407 @@
+#[cfg(feature = "event_routine")]
+use chargrid_event_routine::{
+    common_event::CommonEvent, event_or_peek_with_handled, EventOrPeek, EventRoutine,
+    EventRoutineView, Handled,
+};
+use chargrid_render::*;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransitionDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransitionEffect {
+    /// Both views move in the given direction, with the new view pushing the
+    /// old view out of the way
+    Slide(TransitionDirection),
+    /// Neither view moves, and the boundary between them sweeps across the
+    /// area in the given direction
+    Wipe(TransitionDirection),
+    /// The old view's colours fade to `via`, and then the new view's colours
+    /// fade in from `via`
+    CrossFade { via: Rgb24 },
+}
+
+/// A colour modifier which linearly interpolates colours towards a given
+/// colour. When `by` is 0 colours are unchanged, and when it's 255 every
+/// colour is replaced with `to`.
+#[derive(Debug, Clone, Copy)]
+pub struct ColModifyInterpolate {
+    pub to: Rgb24,
+    pub by: u8,
+}
+
+impl ColModify for ColModifyInterpolate {
+    fn foreground(&self, rgb24: Option<Rgb24>) -> Option<Rgb24> {
+        rgb24.map(|rgb24| rgb24.linear_interpolate(self.to, self.by))
+    }
+    fn background(&self, rgb24: Option<Rgb24>) -> Option<Rgb24> {
+        rgb24.map(|rgb24| rgb24.linear_interpolate(self.to, self.by))
+    }
+}
+
+/// Discards any cells outside a rectangle
+struct ClipFrame<'a, F> {
+    top_left: Coord,
+    size: Size,
+    frame: &'a mut F,
+}
+
+impl<'a, F> Frame for ClipFrame<'a, F>
+where
+    F: Frame,
+{
+    fn set_cell_absolute(
+        &mut self,
+        absolute_coord: Coord,
+        absolute_depth: i8,
+        absolute_cell: ViewCell,
+    ) {
+        if (absolute_coord - self.top_left).is_valid(self.size) {
+            self.frame
+                .set_cell_absolute(absolute_coord, absolute_depth, absolute_cell);
+        }
+    }
+
+    fn blend_cell_background_absolute<B: Blend>(
+        &mut self,
+        absolute_coord: Coord,
+        absolute_depth: i8,
+        rgb24: Rgb24,
+        alpha: u8,
+        blend: B,
+    ) {
+        if (absolute_coord - self.top_left).is_valid(self.size) {
+            self.frame.blend_cell_background_absolute(
+                absolute_coord,
+                absolute_depth,
+                rgb24,
+                alpha,
+                blend,
+            );
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Screen {
+    From,
+    To,
+}
+
+fn progress_ratio_u8(elapsed: Duration, duration: Duration) -> u8 {
+    if elapsed >= duration {
+        255
+    } else {
+        ((elapsed.as_nanos() * 255) / duration.as_nanos()) as u8
+    }
+}
+
+fn scale(length: u32, ratio: u8) -> i32 {
+    ((length as u64 * ratio as u64) / 255) as i32
+}
+
+type TransitionContext<C> = ViewContext<ColModifyCompose<ColModifyInterpolate, C>>;
+
+fn render_transition<C, F, R>(
+    effect: TransitionEffect,
+    progress: u8,
+    context: ViewContext<C>,
+    frame: &mut F,
+    mut render: R,
+) where
+    C: ColModify,
+    F: Frame,
+    R: FnMut(Screen, TransitionContext<C>, &mut ClipFrame<F>),
+{
+    let no_change = ColModifyInterpolate {
+        to: Rgb24::new_grey(0),
+        by: 0,
+    };
+    let width = context.size.width();
+    let height = context.size.height();
+    match effect {
+        TransitionEffect::Slide(direction) => {
+            let (dx, dy) = match direction {
+                TransitionDirection::Left => (-scale(width, progress), 0),
+                TransitionDirection::Right => (scale(width, progress), 0),
+                TransitionDirection::Up => (0, -scale(height, progress)),
+                TransitionDirection::Down => (0, scale(height, progress)),
+            };
+            let (to_x, to_y) = match direction {
+                TransitionDirection::Left => (width as i32 + dx, 0),
+                TransitionDirection::Right => (dx - width as i32, 0),
+                TransitionDirection::Up => (0, height as i32 + dy),
+                TransitionDirection::Down => (0, dy - height as i32),
+            };
+            let mut clip_frame = ClipFrame {
+                top_left: context.offset,
+                size: context.size,
+                frame,
+            };
+            for &(screen, offset) in &[
+                (Screen::From, Coord::new(dx, dy)),
+                (Screen::To, Coord::new(to_x, to_y)),
+            ] {
+                let child_context = ViewContext {
+                    offset: context.offset + offset,
+                    ..context.compose_col_modify(no_change)
+                };
+                render(screen, child_context, &mut clip_frame);
+            }
+        }
+        TransitionEffect::Wipe(direction) => {
+            let (to_top_left, to_size) = match direction {
+                TransitionDirection::Left => {
+                    let covered = scale(width, progress);
+                    (
+                        Coord::new(width as i32 - covered, 0),
+                        Size::new(covered as u32, height),
+                    )
+                }
+                TransitionDirection::Right => (
+                    Coord::new(0, 0),
+                    Size::new(scale(width, progress) as u32, height),
+                ),
+                TransitionDirection::Up => {
+                    let covered = scale(height, progress);
+                    (
+                        Coord::new(0, height as i32 - covered),
+                        Size::new(width, covered as u32),
+                    )
+                }
+                TransitionDirection::Down => (
+                    Coord::new(0, 0),
+                    Size::new(width, scale(height, progress) as u32),
+                ),
+            };
+            let child_context = context.compose_col_modify(no_change);
+            let mut from_frame = ClipFrame {
+                top_left: context.offset,
+                size: context.size,
+                frame,
+            };
+            render(Screen::From, child_context, &mut from_frame);
+            let mut to_frame = ClipFrame {
+                top_left: context.offset + to_top_left,
+                size: to_size,
+                frame: from_frame.frame,
+            };
+            // clear the covered area so the old view doesn't show through any
+            // cells left undrawn by the new view
+            let blank = ViewCell::new()
+                .with_character(' ')
+                .with_bold(false)
+                .with_underline(false)
+                .with_foreground(Rgb24::new_grey(0))
+                .with_background(Rgb24::new_grey(0));
+            for y in 0..(to_size.height() as i32) {
+                for x in 0..(to_size.width() as i32) {
+                    to_frame.set_cell_absolute(
+                        context.offset + to_top_left + Coord::new(x, y),
+                        context.depth + 1,
+                        blank,
+                    );
+                }
+            }
+            render(Screen::To, child_context.add_depth(1), &mut to_frame);
+        }
+        TransitionEffect::CrossFade { via } => {
+            let (screen, by) = if progress < 128 {
+                (Screen::From, progress.saturating_mul(2))
+            } else {
+                (Screen::To, (255 - progress).saturating_mul(2))
+            };
+            let mut clip_frame = ClipFrame {
+                top_left: context.offset,
+                size: context.size,
+                frame,
+            };
+            render(
+                screen,
+                context.compose_col_modify(ColModifyInterpolate { to: via, by }),
+                &mut clip_frame,
+            );
+        }
+    }
+}
+
+/// Render a transition from one view to another. The transition is complete
+/// when `elapsed` reaches `duration`.
+pub struct TransitionView<A, B> {
+    pub from: A,
+    pub to: B,
+    pub effect: TransitionEffect,
+    pub duration: Duration,
+    pub elapsed: Duration,
+}
+
+impl<A, B, T> View<T> for TransitionView<A, B>
+where
+    A: View<T>,
+    B: View<T>,
+    T: Clone,
+{
+    fn view<F: Frame, C: ColModify>(&mut self, data: T, context: ViewContext<C>, frame: &mut F) {
+        let Self {
+            from,
+            to,
+            effect,
+            duration,
+            elapsed,
+        } = self;
+        let progress = progress_ratio_u8(*elapsed, *duration);
+        render_transition(
+            *effect,
+            progress,
+            context,
+            frame,
+            |screen, context, frame| match screen {
+                Screen::From => from.view(data.clone(), context, frame),
+                Screen::To => to.view(data.clone(), context, frame),
+            },
+        );
+    }
+
+    fn size<C: ColModify>(&mut self, _data: T, context: ViewContext<C>) -> Size {
+        context.size
+    }
+}
+
+/// An event routine which renders a transition between the views of two
+/// other event routines, advancing on each `CommonEvent::Frame`. Inputs
+/// received during the transition are ignored. Once the transition is
+/// complete, the routine being transitioned to is returned, so it can be
+/// continued with `and_then(|to| to)`. Requires the `event_routine` feature.
+#[cfg(feature = "event_routine")]
+pub struct TransitionRoutine<A, B> {
+    from: A,
+    to: B,
+    effect: TransitionEffect,
+    duration: Duration,
+    elapsed: Duration,
+}
+
+#[cfg(feature = "event_routine")]
+impl<A, B> TransitionRoutine<A, B>
+where
+    A: EventRoutine<Event = CommonEvent>,
+    B: EventRoutine<Data = A::Data, View = A::View, Event = CommonEvent>,
+{
+    pub fn new(from: A, to: B, effect: TransitionEffect, duration: Duration) -> Self {
+        Self {
+            from,
+            to,
+            effect,
+            duration,
+            elapsed: Duration::from_millis(0),
+        }
+    }
+}
+
+#[cfg(feature = "event_routine")]
+impl<A, B> EventRoutine for TransitionRoutine<A, B>
+where
+    A: EventRoutine<Event = CommonEvent>,
+    B: EventRoutine<Data = A::Data, View = A::View, Event = CommonEvent>,
+{
+    type Return = B;
+    type Data = A::Data;
+    type View = A::View;
+    type Event = CommonEvent;
+
+    fn handle<EP>(
+        self,
+        _data: &mut Self::Data,
+        _view: &Self::View,
+        event_or_peek: EP,
+    ) -> Handled<Self::Return, Self>
+    where
+        EP: EventOrPeek<Event = Self::Event>,
+    {
+        event_or_peek_with_handled(event_or_peek, self, |s, event| match event {
+            CommonEvent::Input(_) => Handled::Continue(s),
+            CommonEvent::Frame(duration) => {
+                let elapsed = s.elapsed + duration;
+                if elapsed >= s.duration {
+                    Handled::Return(s.to)
+                } else {
+                    Handled::Continue(Self { elapsed, ..s })
+                }
+            }
+        })
+    }
+
+    fn view<F, C>(
+        &self,
+        data: &Self::Data,
+        view: &mut Self::View,
+        context: ViewContext<C>,
+        frame: &mut F,
+    ) where
+        F: Frame,
+        C: ColModify,
+    {
+        let progress = progress_ratio_u8(self.elapsed, self.duration);
+        render_transition(
+            self.effect,
+            progress,
+            context,
+            frame,
+            |screen, context, frame| match screen {
+                Screen::From => EventRoutineView {
+                    event_routine: &self.from,
+                    view,
+                }
+                .view(data, context, frame),
+                Screen::To => EventRoutineView {
+                    event_routine: &self.to,
+                    view,
+                }
+                .view(data, context, frame),
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_view::*;
+
+    fn wipe(direction: TransitionDirection) -> Vec<String> {
+        let mut buffer = Buffer::new(Size::new(6, 2));
+        let context = ViewContext::default_with_size(buffer.size());
+        TransitionView {
+            from: Fill::new(6, 2, 'a'),
+            to: Fill::new(1, 1, 'b'),
+            effect: TransitionEffect::Wipe(direction),
+            duration: Duration::from_secs(2),
+            elapsed: Duration::from_secs(1),
+        }
+        .view((), context, &mut buffer);
+        rows(&buffer)
+    }
+
+    #[test]
+    fn sub_microsecond_duration() {
+        let duration = Duration::from_nanos(500);
+        assert_eq!(progress_ratio_u8(Duration::from_nanos(250), duration), 127);
+        assert_eq!(progress_ratio_u8(duration, duration), 255);
+    }
+
+    #[test]
+    fn wipe_clears_covered_cells() {
+        assert_eq!(wipe(TransitionDirection::Right), &["b aaaa", "  aaaa"]);
+        assert_eq!(wipe(TransitionDirection::Left), &["aaaa  ", "aaaa  "]);
+    }
+}
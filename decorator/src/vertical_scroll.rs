@@ -99,6 +99,17 @@ impl VerticalScrollState {
     pub fn scroll_down_page(&mut self, limits: VerticalScrollLimits) {
         self.scroll_down_lines(limits.last_rendered_outer_height as u32, limits);
     }
+    /// Scroll the minimum distance required for the given line to be visible.
+    /// This doesn't clamp to the maximum scroll position, as the line may not
+    /// have been rendered yet.
+    pub fn scroll_to_show_line(&mut self, line: u32, limits: VerticalScrollLimits) {
+        let outer_height = limits.last_rendered_outer_height;
+        if line < self.scroll_position {
+            self.scroll_position = line;
+        } else if outer_height > 0 && line >= self.scroll_position + outer_height {
+            self.scroll_position = line + 1 - outer_height;
+        }
+    }
     pub fn scroll_to_top(&mut self, limits: VerticalScrollLimits) {
         let _ = limits;
         self.scroll_position = 0;
@@ -1,6 +1,8 @@
+use crate::layout::view_entries;
+use crate::scroll::MenuScroll;
 use crate::{
-    MenuEntryRichString, MenuEntryToRender, MenuIndexFromScreenCoord, MenuInstance,
-    MenuInstanceChoose, MenuInstanceMouseTracker,
+    MenuEntryRichString, MenuIndexFromScreenCoord, MenuInstance, MenuInstanceChoose,
+    MenuInstanceMouseTracker, MenuScrollIndicators,
 };
 use chargrid_event_routine::{event_or_peek_with_handled, EventOrPeek, EventRoutine, Handled};
 use chargrid_input::Input;
@@ -11,6 +13,7 @@ use std::marker::PhantomData;
 pub struct DynamicStyleMenuInstanceView {
     mouse_tracker: MenuInstanceMouseTracker,
     buf: String,
+    scroll: MenuScroll,
 }
 
 impl DynamicStyleMenuInstanceView {
@@ -18,8 +21,14 @@ impl DynamicStyleMenuInstanceView {
         Self {
             buf: String::new(),
             mouse_tracker: Default::default(),
+            scroll: Default::default(),
         }
     }
+
+    pub fn with_scroll_indicators(mut self, scroll_indicators: MenuScrollIndicators) -> Self {
+        self.scroll.indicators = Some(scroll_indicators);
+        self
+    }
 }
 
 impl Default for DynamicStyleMenuInstanceView {
@@ -51,23 +60,38 @@ where
         context: ViewContext<C>,
         frame: &mut F,
     ) {
-        self.mouse_tracker.new_frame(context.offset);
-        for (i, entry, maybe_selected) in menu_instance.enumerate() {
-            self.buf.clear();
-            let entry_to_render = MenuEntryToRender {
-                index: i,
-                entry,
-                selected: maybe_selected.is_some(),
-            };
-            let style = menu_entry_rich_string.render_rich_string(entry_to_render, &mut self.buf);
-            let mut view = StringViewSingleLine::new(style);
-            let size = view.view_size(
-                &self.buf,
-                context.add_offset(Coord::new(0, i as i32)),
-                frame,
-            );
-            self.mouse_tracker.on_entry_view_size(size);
-        }
+        let mut width_buf = String::new();
+        let mut entry_width = |i: usize| {
+            width_buf.clear();
+            menu_entry_rich_string
+                .render_rich_string(menu_instance.entry_to_render(i), &mut width_buf);
+            width_buf.chars().count() as u32
+        };
+        let window = self.scroll.update_for_layout(
+            menu_instance.layout(),
+            menu_instance.len(),
+            menu_instance.index(),
+            context.size,
+            &mut entry_width,
+        );
+        self.mouse_tracker.new_frame(context.offset + window.origin);
+        self.mouse_tracker
+            .set_scroll_window(window.indices.start, window.indices.len());
+        let buf = &mut self.buf;
+        view_entries(
+            menu_instance.layout(),
+            &window,
+            context,
+            &mut self.mouse_tracker,
+            &mut entry_width,
+            |i, entry_context| {
+                buf.clear();
+                let style = menu_entry_rich_string
+                    .render_rich_string(menu_instance.entry_to_render(i), buf);
+                StringViewSingleLine::new(style).view_size(&*buf, entry_context, frame)
+            },
+        );
+        self.scroll.render_indicators(&window, context, frame);
     }
 }
 
@@ -75,6 +99,9 @@ impl MenuIndexFromScreenCoord for DynamicStyleMenuInstanceView {
     fn menu_index_from_screen_coord(&self, len: usize, coord: Coord) -> Option<usize> {
         self.mouse_tracker.menu_index_from_screen_coord(len, coord)
     }
+    fn menu_page_size(&self) -> Option<usize> {
+        self.mouse_tracker.page_size()
+    }
 }
 
 pub struct DynamicStyleMenuInstanceRoutine<C, S> {
@@ -0,0 +1,222 @@
+use crate::scroll::MenuScroll;
+use crate::{
+    MenuEntryToRender, MenuEntryView, MenuIndexFromScreenCoord, MenuInstance, MenuInstanceChoose,
+    MenuInstanceMouseTracker, MenuItemId, MenuScrollIndicators,
+};
+use chargrid_event_routine::{event_or_peek_with_handled, EventOrPeek, EventRoutine, Handled};
+use chargrid_input::Input;
+use chargrid_render::*;
+use std::collections::{HashMap, HashSet};
+use std::marker::PhantomData;
+use std::ops::Range;
+
+struct MenuEntryViewAdapter<'v, V>(&'v V);
+
+impl<'a, 'v, V> View<MenuEntryToRender<'a, V::Entry>> for MenuEntryViewAdapter<'v, V>
+where
+    V: MenuEntryView,
+{
+    fn view<F: Frame, C: ColModify>(
+        &mut self,
+        entry: MenuEntryToRender<'a, V::Entry>,
+        context: ViewContext<C>,
+        frame: &mut F,
+    ) {
+        self.0.view_entry(entry, context, frame);
+    }
+}
+
+/// Menu view whose entries are rendered by a `MenuEntryView`, and may have
+/// different heights. Entries are laid out in a vertical list. Entries are
+/// only measured when they are near the visible part of the menu, and their
+/// sizes are remembered between frames. Visible entries are measured again
+/// each frame in case their contents changed.
+pub struct EntryViewMenuInstanceView {
+    mouse_tracker: MenuInstanceMouseTracker,
+    entry_sizes: HashMap<MenuItemId, Size>,
+    entry_sizes_width: u32,
+    visible: Range<usize>,
+    scroll: MenuScroll,
+}
+
+impl EntryViewMenuInstanceView {
+    pub fn new() -> Self {
+        Self {
+            mouse_tracker: Default::default(),
+            entry_sizes: HashMap::new(),
+            entry_sizes_width: 0,
+            visible: 0..0,
+            scroll: Default::default(),
+        }
+    }
+
+    pub fn with_scroll_indicators(mut self, scroll_indicators: MenuScrollIndicators) -> Self {
+        self.scroll.indicators = Some(scroll_indicators);
+        self
+    }
+}
+
+impl Default for EntryViewMenuInstanceView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct EntryViewMenuInstanceModel<'a, E, V>
+where
+    E: Clone,
+    V: MenuEntryView<Entry = E>,
+{
+    menu_instance: &'a MenuInstance<E>,
+    menu_entry_view: &'a V,
+}
+
+impl<'a, E, V> View<EntryViewMenuInstanceModel<'a, E, V>> for EntryViewMenuInstanceView
+where
+    E: Clone,
+    V: MenuEntryView<Entry = E>,
+{
+    fn view<F: Frame, C: ColModify>(
+        &mut self,
+        EntryViewMenuInstanceModel {
+            menu_instance,
+            menu_entry_view,
+        }: EntryViewMenuInstanceModel<'a, E, V>,
+        context: ViewContext<C>,
+        frame: &mut F,
+    ) {
+        let mut adapter = MenuEntryViewAdapter(menu_entry_view);
+        if self.entry_sizes_width != context.size.width() {
+            self.entry_sizes_width = context.size.width();
+            self.entry_sizes.clear();
+        }
+        if self.entry_sizes.len() > menu_instance.len() {
+            let ids = (0..menu_instance.len())
+                .map(|i| menu_instance.id_at(i))
+                .collect::<HashSet<_>>();
+            self.entry_sizes.retain(|id, _| ids.contains(id));
+        }
+        for i in self.visible.start..self.visible.end.min(menu_instance.len()) {
+            let size = measure_size(&mut adapter, menu_instance.entry_to_render(i), context);
+            self.entry_sizes.insert(menu_instance.id_at(i), size);
+        }
+        let entry_sizes = &mut self.entry_sizes;
+        let mut entry_size = |i: usize| {
+            *entry_sizes
+                .entry(menu_instance.id_at(i))
+                .or_insert_with(|| {
+                    measure_size(
+                        &mut MenuEntryViewAdapter(menu_entry_view),
+                        menu_instance.entry_to_render(i),
+                        context,
+                    )
+                })
+        };
+        let window = self.scroll.update_with_entry_heights(
+            menu_instance.len(),
+            menu_instance.index(),
+            context.size.height(),
+            |i| entry_size(i).height(),
+        );
+        self.visible = window.indices.clone();
+        self.mouse_tracker.new_frame(context.offset + window.origin);
+        self.mouse_tracker
+            .set_scroll_window(window.indices.start, window.indices.len());
+        let mut row = window.origin.y;
+        for i in window.indices.clone() {
+            adapter.view(
+                menu_instance.entry_to_render(i),
+                context.add_offset(Coord::new(0, row)),
+                frame,
+            );
+            let size = entry_size(i);
+            self.mouse_tracker.on_entry_view_size(size);
+            row += size.height().max(1) as i32;
+        }
+        self.scroll.render_indicators(&window, context, frame);
+    }
+}
+
+impl MenuIndexFromScreenCoord for EntryViewMenuInstanceView {
+    fn menu_index_from_screen_coord(&self, len: usize, coord: Coord) -> Option<usize> {
+        self.mouse_tracker.menu_index_from_screen_coord(len, coord)
+    }
+    fn menu_page_size(&self) -> Option<usize> {
+        self.mouse_tracker.page_size()
+    }
+}
+
+pub struct EntryViewMenuInstanceRoutine<C, V> {
+    choose: PhantomData<C>,
+    menu_entry_view: V,
+}
+impl<C, V> EntryViewMenuInstanceRoutine<C, V>
+where
+    C: MenuInstanceChoose,
+    V: MenuEntryView<Entry = C::Entry>,
+{
+    pub fn new(menu_entry_view: V) -> Self {
+        Self {
+            choose: PhantomData,
+            menu_entry_view,
+        }
+    }
+}
+impl<C, V> Clone for EntryViewMenuInstanceRoutine<C, V>
+where
+    C: MenuInstanceChoose,
+    V: MenuEntryView<Entry = C::Entry> + Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            choose: PhantomData,
+            menu_entry_view: self.menu_entry_view.clone(),
+        }
+    }
+}
+
+impl<C, V> EventRoutine for EntryViewMenuInstanceRoutine<C, V>
+where
+    C: MenuInstanceChoose,
+    V: MenuEntryView<Entry = C::Entry> + Clone,
+{
+    type Return = C::Output;
+    type Data = C;
+    type View = EntryViewMenuInstanceView;
+    type Event = Input;
+
+    fn handle<EP>(
+        self,
+        data: &mut Self::Data,
+        view: &Self::View,
+        event_or_peek: EP,
+    ) -> Handled<Self::Return, Self>
+    where
+        EP: EventOrPeek<Event = Self::Event>,
+    {
+        event_or_peek_with_handled(event_or_peek, self, |s, event| {
+            if let Some(menu_output) = data.choose(view, event) {
+                Handled::Return(menu_output)
+            } else {
+                Handled::Continue(s)
+            }
+        })
+    }
+
+    fn view<F, CM>(
+        &self,
+        data: &Self::Data,
+        view: &mut Self::View,
+        context: ViewContext<CM>,
+        frame: &mut F,
+    ) where
+        F: Frame,
+        CM: ColModify,
+    {
+        let model = EntryViewMenuInstanceModel {
+            menu_instance: data.menu_instance(),
+            menu_entry_view: &self.menu_entry_view,
+        };
+        view.view(model, context, frame);
+    }
+}
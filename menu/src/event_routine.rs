@@ -11,7 +11,7 @@ pub trait ChooseSelector: DataSelector {
 }
 
 pub struct MenuInstanceRoutine<S> {
-    s: S,
+    pub(crate) s: S,
 }
 impl<S> MenuInstanceRoutine<S>
 where
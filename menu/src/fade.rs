@@ -1,13 +1,15 @@
+use crate::layout::view_entries;
+use crate::scroll::MenuScroll;
 use crate::{
-    MenuEntryString, MenuEntryToRender, MenuIndexFromScreenCoord, MenuInstance, MenuInstanceChoose,
-    MenuInstanceMouseTracker, Selected,
+    MenuEntryString, MenuIndexFromScreenCoord, MenuInstance, MenuInstanceChoose,
+    MenuInstanceMouseTracker, MenuItemId, MenuScrollIndicators, Selected,
 };
 use chargrid_event_routine::{
     common_event, event_or_peek_with_handled, EventOrPeek, EventRoutine, Handled,
 };
 use chargrid_render::{ColModify, Coord, Frame, Rgb24, Style, View, ViewContext};
 use chargrid_text::StringViewSingleLine;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::marker::PhantomData;
 use std::time::Duration;
 
@@ -136,12 +138,16 @@ pub mod fade_spec {
     }
 }
 
+/// Fades entries between the normal and selected styles. Fades are tracked
+/// by the identity of each item, so they carry over when the menu's items are
+/// changed with `MenuInstance::replace_items`.
 pub struct FadeMenuInstanceView {
-    last_change: HashMap<usize, MenuEntryChange>,
+    last_change: HashMap<MenuItemId, MenuEntryChange>,
     previous_view_since_epoch: Duration,
     spec: fade_spec::Spec,
     mouse_tracker: MenuInstanceMouseTracker,
     buf: String,
+    scroll: MenuScroll,
 }
 
 impl FadeMenuInstanceView {
@@ -152,8 +158,13 @@ impl FadeMenuInstanceView {
             spec,
             mouse_tracker: Default::default(),
             buf: String::new(),
+            scroll: Default::default(),
         }
     }
+    pub fn with_scroll_indicators(mut self, scroll_indicators: MenuScrollIndicators) -> Self {
+        self.scroll.indicators = Some(scroll_indicators);
+        self
+    }
     pub fn clear(&mut self) {
         self.last_change.clear();
     }
@@ -184,66 +195,97 @@ where
         context: ViewContext<C>,
         frame: &mut F,
     ) {
-        self.mouse_tracker.new_frame(context.offset);
-        let spec = &self.spec;
-        for (i, entry, maybe_selected) in menu_instance.enumerate() {
-            let current_style = if let Some(Selected) = maybe_selected {
-                &spec.selected
-            } else {
-                &spec.normal
-            };
-            let current = self
-                .last_change
-                .entry(i)
-                .or_insert_with(|| MenuEntryChange {
-                    change_to: maybe_selected,
-                    foreground: FadeInstance::constant(current_style.to.foreground),
-                    background: FadeInstance::constant(current_style.to.background),
-                });
-            match (current.change_to, maybe_selected) {
-                (None, None) | (Some(Selected), Some(Selected)) => (),
-                (Some(Selected), None) => {
-                    current.change_to = None;
-                    current.foreground = current
-                        .foreground
-                        .transform_foreground(&spec.normal, since_epoch);
-                    current.background = current
-                        .background
-                        .transform_background(&spec.normal, since_epoch);
-                }
-                (None, Some(Selected)) => {
-                    current.change_to = Some(Selected);
-                    current.foreground = current
-                        .foreground
-                        .transform_foreground(&spec.selected, since_epoch);
-                    current.background = current
-                        .background
-                        .transform_background(&spec.selected, since_epoch);
-                }
-            }
-            let foreground = current.foreground.current(since_epoch);
-            let background = current.background.current(since_epoch);
-            let entry_to_render = MenuEntryToRender {
-                index: i,
-                entry,
-                selected: maybe_selected.is_some(),
-            };
-            self.buf.clear();
-            menu_entry_string.render_string(entry_to_render, &mut self.buf);
-            let mut view = StringViewSingleLine::new(
-                Style::new()
-                    .with_bold(spec.normal.to.bold)
-                    .with_underline(spec.normal.to.underline)
-                    .with_foreground(foreground)
-                    .with_background(background),
-            );
-            let size = view.view_size(
-                &self.buf,
-                context.add_offset(Coord::new(0, i as i32)),
-                frame,
-            );
-            self.mouse_tracker.on_entry_view_size(size);
+        if self.last_change.len() > menu_instance.len() {
+            let ids = (0..menu_instance.len())
+                .map(|i| menu_instance.id_at(i))
+                .collect::<HashSet<_>>();
+            self.last_change.retain(|id, _| ids.contains(id));
         }
+        let mut width_buf = String::new();
+        let mut entry_width = |i: usize| {
+            width_buf.clear();
+            menu_entry_string.render_string(menu_instance.entry_to_render(i), &mut width_buf);
+            width_buf.chars().count() as u32
+        };
+        let window = self.scroll.update_for_layout(
+            menu_instance.layout(),
+            menu_instance.len(),
+            menu_instance.index(),
+            context.size,
+            &mut entry_width,
+        );
+        self.mouse_tracker.new_frame(context.offset + window.origin);
+        self.mouse_tracker
+            .set_scroll_window(window.indices.start, window.indices.len());
+        let Self {
+            last_change,
+            spec,
+            mouse_tracker,
+            buf,
+            ..
+        } = self;
+        view_entries(
+            menu_instance.layout(),
+            &window,
+            context,
+            mouse_tracker,
+            &mut entry_width,
+            |i, entry_context| {
+                let entry_to_render = menu_instance.entry_to_render(i);
+                let maybe_selected = if entry_to_render.selected {
+                    Some(Selected)
+                } else {
+                    None
+                };
+                let current_style = if let Some(Selected) = maybe_selected {
+                    &spec.selected
+                } else {
+                    &spec.normal
+                };
+                let current =
+                    last_change
+                        .entry(entry_to_render.id)
+                        .or_insert_with(|| MenuEntryChange {
+                            change_to: maybe_selected,
+                            foreground: FadeInstance::constant(current_style.to.foreground),
+                            background: FadeInstance::constant(current_style.to.background),
+                        });
+                match (current.change_to, maybe_selected) {
+                    (None, None) | (Some(Selected), Some(Selected)) => (),
+                    (Some(Selected), None) => {
+                        current.change_to = None;
+                        current.foreground = current
+                            .foreground
+                            .transform_foreground(&spec.normal, since_epoch);
+                        current.background = current
+                            .background
+                            .transform_background(&spec.normal, since_epoch);
+                    }
+                    (None, Some(Selected)) => {
+                        current.change_to = Some(Selected);
+                        current.foreground = current
+                            .foreground
+                            .transform_foreground(&spec.selected, since_epoch);
+                        current.background = current
+                            .background
+                            .transform_background(&spec.selected, since_epoch);
+                    }
+                }
+                let foreground = current.foreground.current(since_epoch);
+                let background = current.background.current(since_epoch);
+                buf.clear();
+                menu_entry_string.render_string(entry_to_render, buf);
+                let mut view = StringViewSingleLine::new(
+                    Style::new()
+                        .with_bold(spec.normal.to.bold)
+                        .with_underline(spec.normal.to.underline)
+                        .with_foreground(foreground)
+                        .with_background(background),
+                );
+                view.view_size(&*buf, entry_context, frame)
+            },
+        );
+        self.scroll.render_indicators(&window, context, frame);
     }
 }
 
@@ -251,6 +293,9 @@ impl MenuIndexFromScreenCoord for FadeMenuInstanceView {
     fn menu_index_from_screen_coord(&self, len: usize, coord: Coord) -> Option<usize> {
         self.mouse_tracker.menu_index_from_screen_coord(len, coord)
     }
+    fn menu_page_size(&self) -> Option<usize> {
+        self.mouse_tracker.page_size()
+    }
 }
 
 pub struct FadeMenuInstanceRoutine<C, S> {
@@ -0,0 +1,518 @@
+use crate::layout::view_entries;
+use crate::scroll::MenuScroll;
+use crate::{
+    Cancel, Escape, MenuAction, MenuEntryRichString, MenuEntryToRender, MenuIndexFromScreenCoord,
+    MenuInstance, MenuInstanceBuilder, MenuInstanceMouseTracker, MenuItemId, MenuItemKind,
+    MenuKeyMap, MenuLayout, MenuScrollIndicators,
+};
+use chargrid_event_routine::{event_or_peek_with_handled, EventOrPeek, EventRoutine, Handled};
+use chargrid_input::{keys, Input, KeyboardInput};
+use chargrid_render::*;
+use chargrid_text::StringViewSingleLine;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// Substring matches always rank above subsequence matches
+const SUBSTRING_SCORE: i32 = 1 << 16;
+
+/// How well a query matches an entry's text. Higher scores are better
+/// matches. Positions are the indices of the matched characters of the text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterMatch {
+    pub score: i32,
+    pub positions: Vec<usize>,
+}
+
+fn chars_eq(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+/// Case-insensitively match a query against some text. Text containing the
+/// query scores higher the earlier the query appears. Otherwise text
+/// containing the characters of the query in order scores lower the more
+/// characters lie between them. Returns `None` if neither is the case.
+pub fn filter_match(query: &str, text: &str) -> Option<FilterMatch> {
+    let query = query.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+    if query.is_empty() {
+        return Some(FilterMatch {
+            score: 0,
+            positions: Vec::new(),
+        });
+    }
+    if query.len() <= text.len() {
+        for start in 0..=(text.len() - query.len()) {
+            if query
+                .iter()
+                .zip(&text[start..])
+                .all(|(&q, &t)| chars_eq(q, t))
+            {
+                return Some(FilterMatch {
+                    score: SUBSTRING_SCORE - start as i32,
+                    positions: (start..(start + query.len())).collect(),
+                });
+            }
+        }
+    }
+    let mut positions = Vec::with_capacity(query.len());
+    let mut text = text.iter().enumerate();
+    for &q in &query {
+        let (i, _) = text.find(|&(_, &t)| chars_eq(q, t))?;
+        positions.push(i);
+    }
+    let span = positions[positions.len() - 1] - positions[0] + 1;
+    Some(FilterMatch {
+        score: -((span - query.len()) as i32),
+        positions,
+    })
+}
+
+/// An item of a filtered menu, along with its index in the unfiltered menu
+/// and the positions of the characters of its text matched by the query
+#[derive(Debug, Clone)]
+pub struct FilteredEntry<T> {
+    pub entry: T,
+    pub index: usize,
+    pub positions: Vec<usize>,
+}
+
+/// A menu whose items are filtered by typing. Printable characters are added
+/// to the query, and backspace removes them. Items are ranked by how well
+/// their text matches the query, with ties kept in their original order.
+/// Hotkeys take precedence over typing while the query is empty, and
+/// separators are hidden while it isn't. Printable characters are typed into
+/// the query even if they are bound in the menu's key map.
+#[derive(Debug, Clone)]
+pub struct FilterMenuInstance<T: Clone> {
+    items: Vec<T>,
+    kinds: Vec<MenuItemKind>,
+    ids: Vec<MenuItemId>,
+    texts: Vec<String>,
+    hotkeys: HashMap<char, T>,
+    item_hotkeys: HashMap<char, usize>,
+    layout: MenuLayout,
+    wrap: bool,
+    key_map: MenuKeyMap,
+    query: String,
+    filtered: Option<MenuInstance<FilteredEntry<T>>>,
+}
+
+impl<T: Clone> FilterMenuInstance<T> {
+    /// Filter the items of a menu by the text returned by `text` for each
+    pub fn new<F: FnMut(&T) -> String>(mut menu_instance: MenuInstance<T>, text: F) -> Self {
+        menu_instance.fill_defaults();
+        let MenuInstance {
+            items,
+            selected_index,
+            kinds,
+            hotkeys,
+            item_hotkeys,
+            layout,
+            wrap,
+            key_map,
+            ids,
+            ..
+        } = menu_instance;
+        let texts = items.iter().map(text).collect();
+        let mut filter_menu_instance = Self {
+            items,
+            kinds,
+            ids,
+            texts,
+            hotkeys,
+            item_hotkeys,
+            layout,
+            wrap,
+            key_map,
+            query: String::new(),
+            filtered: None,
+        };
+        filter_menu_instance.filter(Some(selected_index));
+        filter_menu_instance
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn set_query(&mut self, query: &str) {
+        self.query = query.to_string();
+        self.filter(self.selected_original_index());
+    }
+
+    pub fn clear_query(&mut self) {
+        self.set_query("");
+    }
+
+    /// The items matching the query, or `None` if no items match
+    pub fn filtered(&self) -> Option<&MenuInstance<FilteredEntry<T>>> {
+        self.filtered.as_ref()
+    }
+
+    pub fn selected(&self) -> Option<&T> {
+        self.filtered
+            .as_ref()
+            .map(|filtered| &filtered.selected().entry)
+    }
+
+    fn selected_original_index(&self) -> Option<usize> {
+        self.filtered
+            .as_ref()
+            .map(|filtered| filtered.selected().index)
+    }
+
+    /// Rebuild the filtered menu, keeping the given item selected if it
+    /// still matches
+    fn filter(&mut self, selected_original_index: Option<usize>) {
+        let query = &self.query;
+        let kinds = &self.kinds;
+        let mut matches = self
+            .texts
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| query.is_empty() || kinds[i] != MenuItemKind::Separator)
+            .filter_map(|(i, text)| filter_match(query, text).map(|m| (i, m)))
+            .collect::<Vec<_>>();
+        matches.sort_by_key(|(_, m)| Reverse(m.score));
+        let selected_index = selected_original_index
+            .and_then(|original| matches.iter().position(|&(i, _)| i == original))
+            .unwrap_or(0);
+        let kinds = matches.iter().map(|&(i, _)| kinds[i]).collect();
+        let items = matches
+            .into_iter()
+            .map(|(i, m)| FilteredEntry {
+                entry: self.items[i].clone(),
+                index: i,
+                positions: m.positions,
+            })
+            .collect();
+        self.filtered = MenuInstanceBuilder {
+            items,
+            selected_index,
+            hotkeys: None,
+        }
+        .build()
+        .ok()
+        .map(|filtered| {
+            filtered
+                .with_kinds(kinds)
+                .with_layout(self.layout)
+                .with_wrap(self.wrap)
+                .with_key_map(self.key_map.clone())
+        });
+    }
+
+    pub fn choose<M>(&mut self, view: &M, input: Input) -> Option<T>
+    where
+        M: MenuIndexFromScreenCoord,
+    {
+        match input {
+            Input::Keyboard(KeyboardInput::Char(c))
+                if self.query.is_empty() && self.item_hotkeys.contains_key(&c) =>
+            {
+                let index = self.item_hotkeys[&c];
+                if matches!(self.kinds.get(index), Some(kind) if kind.is_selectable()) {
+                    return Some(self.items[index].clone());
+                }
+            }
+            Input::Keyboard(KeyboardInput::Char(c))
+                if self.query.is_empty() && self.hotkeys.contains_key(&c) =>
+            {
+                return Some(self.hotkeys[&c].clone());
+            }
+            Input::Keyboard(keys::BACKSPACE) => {
+                if self.query.pop().is_some() {
+                    self.filter(self.selected_original_index());
+                }
+            }
+            Input::Keyboard(KeyboardInput::Char(c))
+                if !c.is_control() && (c != ' ' || !self.query.is_empty()) =>
+            {
+                self.query.push(c);
+                self.filter(self.selected_original_index());
+            }
+            other => {
+                if let Some(filtered) = self.filtered.as_mut() {
+                    return filtered.choose(view, other).map(|chosen| chosen.entry);
+                }
+            }
+        }
+        None
+    }
+
+    /// Escape clears the query, or escapes the menu if the query is empty
+    pub fn choose_or_escape<M>(&mut self, view: &M, input: Input) -> Option<Result<T, Escape>>
+    where
+        M: MenuIndexFromScreenCoord,
+    {
+        match self.key_map.action(input) {
+            Some(MenuAction::Escape) => {
+                if self.query.is_empty() {
+                    Some(Err(Escape))
+                } else {
+                    self.clear_query();
+                    None
+                }
+            }
+            _ => self.choose(view, input).map(Ok),
+        }
+    }
+
+    pub fn choose_or_cancel<M>(&mut self, view: &M, input: Input) -> Option<Result<T, Cancel>>
+    where
+        M: MenuIndexFromScreenCoord,
+    {
+        match self.key_map.action(input) {
+            Some(MenuAction::Quit) => Some(Err(Cancel::Quit)),
+            _ => self
+                .choose_or_escape(view, input)
+                .map(|result| result.map_err(|Escape| Cancel::Escape)),
+        }
+    }
+}
+
+/// Renders the query of a `FilterMenuInstance` followed by the matching
+/// items. Entries are rendered with a `MenuEntryRichString`, and the
+/// characters matched by the query are drawn with the highlight style on top
+/// of the entry's style. This assumes that the rich string renders the same
+/// text used to filter the entry.
+pub struct FilterMenuInstanceView {
+    mouse_tracker: MenuInstanceMouseTracker,
+    scroll: MenuScroll,
+    buf: String,
+    prompt: String,
+    query_style: Style,
+    highlight: Style,
+}
+
+impl FilterMenuInstanceView {
+    pub fn new(highlight: Style) -> Self {
+        Self {
+            mouse_tracker: Default::default(),
+            scroll: Default::default(),
+            buf: String::new(),
+            prompt: "> ".to_string(),
+            query_style: Style::new(),
+            highlight,
+        }
+    }
+
+    /// Text drawn before the query
+    pub fn with_prompt(self, prompt: &str) -> Self {
+        Self {
+            prompt: prompt.to_string(),
+            ..self
+        }
+    }
+
+    pub fn with_query_style(self, query_style: Style) -> Self {
+        Self {
+            query_style,
+            ..self
+        }
+    }
+
+    pub fn with_scroll_indicators(mut self, scroll_indicators: MenuScrollIndicators) -> Self {
+        self.scroll.indicators = Some(scroll_indicators);
+        self
+    }
+}
+
+pub struct FilterMenuInstanceModel<'a, T, S>
+where
+    T: Clone,
+    S: MenuEntryRichString<Entry = T>,
+{
+    pub filter_menu_instance: &'a FilterMenuInstance<T>,
+    pub menu_entry_rich_string: &'a S,
+}
+
+impl<'a, T, S> View<FilterMenuInstanceModel<'a, T, S>> for FilterMenuInstanceView
+where
+    T: Clone,
+    S: MenuEntryRichString<Entry = T>,
+{
+    fn view<F: Frame, C: ColModify>(
+        &mut self,
+        FilterMenuInstanceModel {
+            filter_menu_instance,
+            menu_entry_rich_string,
+        }: FilterMenuInstanceModel<'a, T, S>,
+        context: ViewContext<C>,
+        frame: &mut F,
+    ) {
+        self.buf.clear();
+        self.buf.push_str(&self.prompt);
+        self.buf.push_str(&filter_menu_instance.query);
+        StringViewSingleLine::new(self.query_style).view(&self.buf, context, frame);
+        let context = context.add_offset(Coord::new(0, 1));
+        self.mouse_tracker.new_frame(context.offset);
+        let filtered = match filter_menu_instance.filtered.as_ref() {
+            Some(filtered) => filtered,
+            None => return,
+        };
+        let entry_to_render = |i: usize| {
+            let filtered_entry = &filtered.items[i];
+            MenuEntryToRender {
+                entry: &filtered_entry.entry,
+                selected: i == filtered.index(),
+                index: filtered_entry.index,
+                id: filter_menu_instance.ids[filtered_entry.index],
+                kind: filtered.kind_at(i),
+            }
+        };
+        let mut width_buf = String::new();
+        let mut entry_width = |i: usize| {
+            width_buf.clear();
+            menu_entry_rich_string.render_rich_string(entry_to_render(i), &mut width_buf);
+            width_buf.chars().count() as u32
+        };
+        let window = self.scroll.update_for_layout(
+            filtered.layout(),
+            filtered.len(),
+            filtered.index(),
+            context.size,
+            &mut entry_width,
+        );
+        self.mouse_tracker.new_frame(context.offset + window.origin);
+        self.mouse_tracker
+            .set_scroll_window(window.indices.start, window.indices.len());
+        let Self {
+            mouse_tracker,
+            buf,
+            highlight,
+            ..
+        } = self;
+        view_entries(
+            filtered.layout(),
+            &window,
+            context,
+            mouse_tracker,
+            &mut entry_width,
+            |i, entry_context| {
+                buf.clear();
+                let style = menu_entry_rich_string.render_rich_string(entry_to_render(i), buf);
+                let positions = &filtered.items[i].positions;
+                let mut width = 0;
+                for (j, character) in buf.chars().enumerate() {
+                    let style = if positions.contains(&j) {
+                        highlight.coalesce(style)
+                    } else {
+                        style
+                    };
+                    let view_cell = ViewCell::new().with_character(character).with_style(style);
+                    frame.set_cell_relative(Coord::new(j as i32, 0), 0, view_cell, entry_context);
+                    width += 1;
+                }
+                Size::new(width, 1)
+            },
+        );
+        self.scroll.render_indicators(&window, context, frame);
+    }
+}
+
+impl MenuIndexFromScreenCoord for FilterMenuInstanceView {
+    fn menu_index_from_screen_coord(&self, len: usize, coord: Coord) -> Option<usize> {
+        self.mouse_tracker.menu_index_from_screen_coord(len, coord)
+    }
+    fn menu_page_size(&self) -> Option<usize> {
+        self.mouse_tracker.page_size()
+    }
+}
+
+/// Runs a filterable menu until an item is chosen or the menu is cancelled
+#[derive(Clone)]
+pub struct FilterMenuInstanceRoutine<S> {
+    menu_entry_rich_string: S,
+}
+
+impl<S> FilterMenuInstanceRoutine<S>
+where
+    S: MenuEntryRichString,
+    S::Entry: Clone,
+{
+    pub fn new(menu_entry_rich_string: S) -> Self {
+        Self {
+            menu_entry_rich_string,
+        }
+    }
+}
+
+impl<S> EventRoutine for FilterMenuInstanceRoutine<S>
+where
+    S: MenuEntryRichString,
+    S::Entry: Clone,
+{
+    type Return = Result<S::Entry, Cancel>;
+    type Data = FilterMenuInstance<S::Entry>;
+    type View = FilterMenuInstanceView;
+    type Event = Input;
+
+    fn handle<EP>(
+        self,
+        data: &mut Self::Data,
+        view: &Self::View,
+        event_or_peek: EP,
+    ) -> Handled<Self::Return, Self>
+    where
+        EP: EventOrPeek<Event = Self::Event>,
+    {
+        event_or_peek_with_handled(event_or_peek, self, |s, event| {
+            if let Some(menu_output) = data.choose_or_cancel(view, event) {
+                Handled::Return(menu_output)
+            } else {
+                Handled::Continue(s)
+            }
+        })
+    }
+
+    fn view<F, CM>(
+        &self,
+        data: &Self::Data,
+        view: &mut Self::View,
+        context: ViewContext<CM>,
+        frame: &mut F,
+    ) where
+        F: Frame,
+        CM: ColModify,
+    {
+        let model = FilterMenuInstanceModel {
+            filter_menu_instance: data,
+            menu_entry_rich_string: &self.menu_entry_rich_string,
+        };
+        view.view(model, context, frame);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ranking() {
+        assert_eq!(
+            filter_match("fir", "Fireball").unwrap().positions,
+            [0, 1, 2]
+        );
+        assert_eq!(filter_match("fb", "Fireball").unwrap().positions, [0, 4]);
+        assert!(filter_match("fz", "Fireball").is_none());
+        let menu_instance =
+            MenuInstance::new(vec!["Frost bolt", "Fireball", "Fire shield"]).unwrap();
+        let mut filter_menu_instance = FilterMenuInstance::new(menu_instance, |s| s.to_string());
+        filter_menu_instance.set_query("fi");
+        let filtered = filter_menu_instance
+            .filtered()
+            .unwrap()
+            .items
+            .iter()
+            .map(|filtered_entry| filtered_entry.entry)
+            .collect::<Vec<_>>();
+        assert_eq!(filtered, ["Fireball", "Fire shield"]);
+        filter_menu_instance.set_query("fb");
+        assert_eq!(filter_menu_instance.selected(), Some(&"Fireball"));
+        filter_menu_instance.set_query("fbo");
+        assert_eq!(filter_menu_instance.selected(), Some(&"Frost bolt"));
+        filter_menu_instance.set_query("q");
+        assert!(filter_menu_instance.selected().is_none());
+    }
+}
@@ -1,9 +1,12 @@
-use chargrid_input::{keys, Input, KeyboardInput, MouseInput, ScrollDirection};
+use crate::layout::num_rows;
+use crate::{MenuAction, MenuEntryToRender, MenuKeyMap, MenuLayout};
+use chargrid_input::{Input, KeyboardInput, MouseInput, ScrollDirection};
 use chargrid_render::Coord;
 use chargrid_render::Size;
 #[cfg(feature = "serialize")]
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::hash::Hash;
 
 #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone)]
@@ -11,6 +14,59 @@ pub struct MenuInstance<T: Clone> {
     pub(crate) items: Vec<T>,
     pub(crate) selected_index: usize,
     pub(crate) hotkeys: HashMap<char, T>,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub(crate) item_hotkeys: HashMap<char, usize>,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub(crate) kinds: Vec<MenuItemKind>,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub(crate) layout: MenuLayout,
+    #[cfg_attr(feature = "serialize", serde(default = "default_wrap"))]
+    pub(crate) wrap: bool,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub(crate) ids: Vec<MenuItemId>,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    next_id: u64,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub(crate) key_map: MenuKeyMap,
+}
+
+#[cfg(feature = "serialize")]
+fn default_wrap() -> bool {
+    true
+}
+
+/// Identifies an item of a menu for as long as it remains in the menu, even
+/// if items are added or removed around it with `MenuInstance::replace_items`.
+/// Views use this to keep per-entry state, such as fades, with the item
+/// rather than its index.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MenuItemId(u64);
+
+/// Whether a menu item can be selected, and how it should be rendered.
+/// Disabled items and separators are skipped by navigation, hotkeys and the
+/// mouse.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuItemKind {
+    Enabled,
+    /// An entry which can't currently be chosen, such as "Load game" when
+    /// there are no saves
+    Disabled,
+    /// A non-selectable divider or heading between groups of entries
+    Separator,
+}
+
+impl Default for MenuItemKind {
+    fn default() -> Self {
+        MenuItemKind::Enabled
+    }
+}
+
+impl MenuItemKind {
+    pub fn is_selectable(self) -> bool {
+        self == MenuItemKind::Enabled
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -40,12 +96,30 @@ pub struct Selected;
 
 pub trait MenuIndexFromScreenCoord {
     fn menu_index_from_screen_coord(&self, len: usize, coord: Coord) -> Option<usize>;
+
+    /// The number of entries visible at once, used to move the selection by
+    /// PageUp and PageDown. If `None`, they jump to the first and last entry.
+    fn menu_page_size(&self) -> Option<usize> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct MenuEntryRect {
+    index: usize,
+    top_left: Coord,
+    size: Size,
+    /// Entries in a vertical list can be pointed at anywhere along their rows
+    extends_to_right_edge: bool,
 }
 
 #[derive(Debug)]
 pub struct MenuInstanceMouseTracker {
     last_offset: Coord,
     last_size: Size,
+    scroll_offset: usize,
+    num_visible: usize,
+    entry_rects: Vec<MenuEntryRect>,
 }
 
 impl Default for MenuInstanceMouseTracker {
@@ -53,6 +127,9 @@ impl Default for MenuInstanceMouseTracker {
         Self {
             last_offset: Coord::new(0, 0),
             last_size: Size::new_u16(0, 0),
+            scroll_offset: 0,
+            num_visible: usize::MAX,
+            entry_rects: Vec::new(),
         }
     }
 }
@@ -61,27 +138,86 @@ impl MenuInstanceMouseTracker {
     pub fn new_frame(&mut self, context_offset: Coord) {
         self.last_offset = context_offset;
         self.last_size = Size::new_u16(0, 0);
+        self.scroll_offset = 0;
+        self.num_visible = usize::MAX;
+        self.entry_rects.clear();
+    }
+    /// Record that only `num_visible` entries starting at `scroll_offset` were
+    /// rendered this frame, with the first at the context offset
+    pub fn set_scroll_window(&mut self, scroll_offset: usize, num_visible: usize) {
+        self.scroll_offset = scroll_offset;
+        self.num_visible = num_visible;
+    }
+    pub fn page_size(&self) -> Option<usize> {
+        if self.num_visible == usize::MAX {
+            None
+        } else {
+            Some(self.num_visible)
+        }
     }
+    /// Record the size of the next entry of a vertical list, which is assumed
+    /// to be drawn directly below the previous entry. Entries occupy at least
+    /// one row.
     pub fn on_entry_view_size(&mut self, size: Size) {
+        let height = size.height().max(1);
+        self.entry_rects.push(MenuEntryRect {
+            index: self.scroll_offset + self.entry_rects.len(),
+            top_left: Coord::new(0, self.last_size.height() as i32),
+            size: size.set_height(height),
+            extends_to_right_edge: true,
+        });
         self.last_size
             .set_width_in_place(self.last_size.width().max(size.width()));
         self.last_size
-            .set_height_in_place(self.last_size.height() + size.height());
+            .set_height_in_place(self.last_size.height() + height);
+    }
+    /// Record the rectangle occupied by an entry, relative to the context
+    /// offset passed to `new_frame`, for views which don't lay entries out
+    /// in a vertical list
+    pub fn on_entry_rect(&mut self, index: usize, top_left: Coord, size: Size) {
+        self.entry_rects.push(MenuEntryRect {
+            index,
+            top_left,
+            size,
+            extends_to_right_edge: false,
+        });
+        let bottom_right = top_left + size.to_coord().unwrap_or_else(|_| Coord::new(0, 0));
+        self.last_size
+            .set_width_in_place(self.last_size.width().max(bottom_right.x.max(0) as u32));
+        self.last_size
+            .set_height_in_place(self.last_size.height().max(bottom_right.y.max(0) as u32));
     }
     pub fn menu_index_from_screen_coord(&self, len: usize, coord: Coord) -> Option<usize> {
         let rel_coord = coord - self.last_offset;
-        if rel_coord.x < 0
-            || rel_coord.y < 0
-            || rel_coord.x >= self.last_size.x() as i32
-            || rel_coord.y >= len as i32
-        {
-            None
-        } else {
-            Some(rel_coord.y as usize)
-        }
+        let width = self.last_size.width() as i32;
+        self.entry_rects
+            .iter()
+            .find(|rect| {
+                let right = if rect.extends_to_right_edge {
+                    width
+                } else {
+                    rect.top_left.x + rect.size.width() as i32
+                };
+                rel_coord.x >= rect.top_left.x
+                    && rel_coord.x < right
+                    && rel_coord.y >= rect.top_left.y
+                    && rel_coord.y < rect.top_left.y + rect.size.height() as i32
+            })
+            .map(|rect| rect.index)
+            .filter(|&index| index < len)
     }
 }
 
+/// Indices less than `len` in order of distance from `target`, alternating
+/// between the index after and before `target` at each distance
+fn outward_from(target: usize, len: usize) -> impl Iterator<Item = usize> {
+    (0..len).flat_map(move |distance| {
+        let after = Some(target + distance).filter(|&index| index < len);
+        let before = target.checked_sub(distance).filter(|_| distance > 0);
+        after.into_iter().chain(before)
+    })
+}
+
 pub struct MenuInstanceBuilder<T: Clone> {
     pub items: Vec<T>,
     pub selected_index: usize,
@@ -98,10 +234,20 @@ impl<T: Clone> MenuInstanceBuilder<T> {
         if selected_index >= items.len() {
             return Err(InitialIndexOutOfBounds);
         }
+        let kinds = vec![MenuItemKind::Enabled; items.len()];
+        let ids = (0..items.len() as u64).map(MenuItemId).collect();
+        let next_id = items.len() as u64;
         Ok(MenuInstance {
+            ids,
+            next_id,
             items,
             selected_index,
+            kinds,
             hotkeys: hotkeys.unwrap_or_default(),
+            item_hotkeys: HashMap::new(),
+            layout: MenuLayout::Vertical,
+            wrap: true,
+            key_map: MenuKeyMap::default(),
         })
     }
 }
@@ -124,27 +270,337 @@ impl<T: Clone> MenuInstance<T> {
         .build()
     }
 
+    /// Fill in the kinds and ids of any items which don't have them, such as
+    /// after deserializing a menu saved before they were added
+    pub(crate) fn fill_defaults(&mut self) {
+        let len = self.items.len();
+        self.kinds.resize(len, MenuItemKind::Enabled);
+        self.next_id = self
+            .next_id
+            .max(self.ids.iter().map(|id| id.0 + 1).max().unwrap_or(0));
+        while self.ids.len() < len {
+            self.ids.push(MenuItemId(self.next_id));
+            self.next_id += 1;
+        }
+        self.ids.truncate(len);
+    }
+
+    /// Replace the items of the menu in place. Each new item whose key
+    /// matches that of an old item takes over its identity, kind and item
+    /// hotkeys, and the selection stays on the same item if it is still
+    /// present. Otherwise the selection moves to the nearest selectable item
+    /// to its old index, preferring the later item when two are equally near.
+    /// Items with duplicate keys are matched in order. Menus can't be empty,
+    /// so replacing the items with an empty list leaves the menu unchanged and
+    /// returns `Err(InitialIndexOutOfBounds)`.
+    pub fn replace_items<K, F>(
+        &mut self,
+        items: Vec<T>,
+        mut key: F,
+    ) -> Result<(), InitialIndexOutOfBounds>
+    where
+        K: Eq + Hash,
+        F: FnMut(&T) -> K,
+    {
+        if items.is_empty() {
+            // there would be no item to select
+            return Err(InitialIndexOutOfBounds);
+        }
+        self.fill_defaults();
+        let mut old_indices_by_key = HashMap::<K, Vec<usize>>::new();
+        for (i, item) in self.items.iter().enumerate().rev() {
+            old_indices_by_key.entry(key(item)).or_default().push(i);
+        }
+        let mut new_indices = vec![None; self.items.len()];
+        let mut ids = Vec::with_capacity(items.len());
+        let mut kinds = Vec::with_capacity(items.len());
+        for (i, item) in items.iter().enumerate() {
+            match old_indices_by_key
+                .get_mut(&key(item))
+                .and_then(|old_indices| old_indices.pop())
+            {
+                Some(old_index) => {
+                    new_indices[old_index] = Some(i);
+                    ids.push(self.ids[old_index]);
+                    kinds.push(self.kinds[old_index]);
+                }
+                None => {
+                    ids.push(MenuItemId(self.next_id));
+                    self.next_id += 1;
+                    kinds.push(MenuItemKind::Enabled);
+                }
+            }
+        }
+        self.item_hotkeys = self
+            .item_hotkeys
+            .iter()
+            .filter_map(|(&c, &index)| Some((c, new_indices.get(index).copied()??)))
+            .collect();
+        let selected_index = new_indices[self.selected_index];
+        let len = items.len();
+        self.items = items;
+        self.ids = ids;
+        self.kinds = kinds;
+        match selected_index {
+            Some(index) if self.is_selectable(index) => self.selected_index = index,
+            _ => {
+                let target = self.selected_index.min(len - 1);
+                self.selected_index = target;
+                self.select_first_selectable(outward_from(target, len));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    /// The identity of the item at an index, or `None` if the index is out
+    /// of bounds
+    pub fn item_id(&self, index: usize) -> Option<MenuItemId> {
+        if index < self.items.len() {
+            Some(self.id_at(index))
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn id_at(&self, index: usize) -> MenuItemId {
+        self.ids
+            .get(index)
+            .copied()
+            .unwrap_or(MenuItemId(index as u64))
+    }
+
+    pub(crate) fn kind_at(&self, index: usize) -> MenuItemKind {
+        self.kinds.get(index).copied().unwrap_or_default()
+    }
+
+    /// Set the kinds of the items at corresponding indices. Items without a
+    /// kind are enabled. If the selected item isn't selectable, the selection
+    /// moves to the next selectable item.
+    pub fn with_kinds(mut self, kinds: Vec<MenuItemKind>) -> Self {
+        self.kinds = kinds;
+        self.kinds.resize(self.items.len(), MenuItemKind::Enabled);
+        if !self.is_selectable(self.selected_index) {
+            self.select_next_selectable();
+        }
+        self
+    }
+
+    /// Bind a key to choosing the item at an index. Unlike the hotkeys passed
+    /// to `MenuInstanceBuilder`, which choose a value regardless of the items
+    /// in the menu, item hotkeys are ignored while their item isn't
+    /// selectable, and follow their item through `replace_items`.
+    pub fn with_item_hotkey(mut self, key: char, index: usize) -> Self {
+        self.set_item_hotkey(key, index);
+        self
+    }
+
+    pub fn set_item_hotkey(&mut self, key: char, index: usize) {
+        self.item_hotkeys.insert(key, index);
+    }
+
+    pub fn with_layout(self, layout: MenuLayout) -> Self {
+        Self { layout, ..self }
+    }
+
+    pub fn set_layout(&mut self, layout: MenuLayout) {
+        self.layout = layout;
+    }
+
+    pub fn layout(&self) -> MenuLayout {
+        self.layout
+    }
+
+    /// Whether moving past the last entry in a row or column selects the
+    /// first, and vice versa. Defaults to true.
+    pub fn with_wrap(self, wrap: bool) -> Self {
+        Self { wrap, ..self }
+    }
+
+    pub fn set_wrap(&mut self, wrap: bool) {
+        self.wrap = wrap;
+    }
+
+    /// Inputs bound to menu actions. Hotkeys are only checked for keys which
+    /// aren't bound to an action.
+    pub fn with_key_map(self, key_map: MenuKeyMap) -> Self {
+        Self { key_map, ..self }
+    }
+
+    pub fn set_key_map(&mut self, key_map: MenuKeyMap) {
+        self.key_map = key_map;
+    }
+
+    pub fn key_map(&self) -> &MenuKeyMap {
+        &self.key_map
+    }
+
+    /// The kind of the item at an index, or `None` if the index is out of
+    /// bounds
+    pub fn kind(&self, index: usize) -> Option<MenuItemKind> {
+        if index < self.items.len() {
+            Some(self.kind_at(index))
+        } else {
+            None
+        }
+    }
+
+    pub fn is_selectable(&self, index: usize) -> bool {
+        self.kind(index).map(MenuItemKind::is_selectable) == Some(true)
+    }
+
+    /// Change the kind of an item, returning its previous kind, or `None` if
+    /// the index is out of bounds. If the selected item stops being
+    /// selectable, the selection moves to the next selectable item.
+    pub fn set_kind(&mut self, index: usize, kind: MenuItemKind) -> Option<MenuItemKind> {
+        let previous = self.kind(index)?;
+        self.kinds.resize(self.items.len(), MenuItemKind::Enabled);
+        self.kinds[index] = kind;
+        if index == self.selected_index && !kind.is_selectable() {
+            self.select_next_selectable();
+        }
+        Some(previous)
+    }
+
+    /// Enable or disable an item. Separators and indices out of bounds are
+    /// unaffected.
+    pub fn set_enabled(&mut self, index: usize, enabled: bool) {
+        if matches!(self.kind(index), Some(kind) if kind != MenuItemKind::Separator) {
+            let kind = if enabled {
+                MenuItemKind::Enabled
+            } else {
+                MenuItemKind::Disabled
+            };
+            self.set_kind(index, kind);
+        }
+    }
+
+    fn select_first_selectable<I: Iterator<Item = usize>>(&mut self, mut indices: I) {
+        if let Some(index) = indices.find(|&index| self.is_selectable(index)) {
+            self.selected_index = index;
+        }
+    }
+
+    fn select_next_selectable(&mut self) {
+        let len = self.items.len();
+        let selected_index = self.selected_index;
+        self.select_first_selectable((1..len).map(|i| (selected_index + i) % len));
+    }
+
+    /// Move the selection by `stride` entries at a time until a selectable
+    /// entry is found, stopping or wrapping around at the end of the column
+    /// containing the selection when entries are arranged in rows of
+    /// `stride` entries
+    fn step(&mut self, stride: usize, forward: bool) {
+        let len = self.items.len();
+        let column = self.selected_index % stride;
+        let row = self.selected_index / stride;
+        let num_rows = num_rows(len, stride);
+        let rows: Vec<usize> = if self.wrap {
+            (1..num_rows)
+                .map(|i| {
+                    if forward {
+                        (row + i) % num_rows
+                    } else {
+                        (row + num_rows - i) % num_rows
+                    }
+                })
+                .collect()
+        } else if forward {
+            (row + 1..num_rows).collect()
+        } else {
+            (0..row).rev().collect()
+        };
+        self.select_first_selectable(
+            rows.into_iter()
+                .map(|row| row * stride + column)
+                .filter(|&index| index < len),
+        );
+    }
+
+    /// Select the previous selectable entry in the order of the items
+    pub fn previous(&mut self) {
+        self.step(1, false);
+    }
+
+    /// Select the next selectable entry in the order of the items
+    pub fn next(&mut self) {
+        self.step(1, true);
+    }
+
     pub fn up(&mut self) {
-        match self.selected_index.checked_sub(1) {
-            Some(index) => self.selected_index = index,
-            None => self.selected_index = self.items.len() - 1,
+        match self.layout {
+            MenuLayout::Vertical => self.step(1, false),
+            MenuLayout::Horizontal { .. } => self.select_first(),
+            MenuLayout::Grid { columns, .. } => self.step(columns.max(1), false),
         }
     }
 
     pub fn down(&mut self) {
-        if self.selected_index < self.items.len() - 1 {
-            self.selected_index += 1;
-        } else {
-            self.selected_index = 0;
+        match self.layout {
+            MenuLayout::Vertical => self.step(1, true),
+            MenuLayout::Horizontal { .. } => self.select_last(),
+            MenuLayout::Grid { columns, .. } => self.step(columns.max(1), true),
+        }
+    }
+
+    pub fn left(&mut self) {
+        match self.layout {
+            MenuLayout::Vertical => (),
+            MenuLayout::Horizontal { .. } | MenuLayout::Grid { .. } => self.step(1, false),
         }
     }
 
+    pub fn right(&mut self) {
+        match self.layout {
+            MenuLayout::Vertical => (),
+            MenuLayout::Horizontal { .. } | MenuLayout::Grid { .. } => self.step(1, true),
+        }
+    }
+
+    /// Select the item at an index if it is selectable
     pub fn set_index(&mut self, index: usize) {
-        if index < self.items.len() {
+        if self.is_selectable(index) {
             self.selected_index = index;
         }
     }
 
+    /// Move the selection up by `page_size` entries, stopping at the first
+    pub fn page_up(&mut self, page_size: usize) {
+        let target = self.selected_index.saturating_sub(page_size.max(1));
+        let len = self.items.len();
+        self.select_first_selectable((0..=target).rev().chain(target + 1..len));
+    }
+
+    /// Move the selection down by `page_size` entries, stopping at the last
+    pub fn page_down(&mut self, page_size: usize) {
+        let target = self
+            .selected_index
+            .saturating_add(page_size.max(1))
+            .min(self.items.len() - 1);
+        let len = self.items.len();
+        self.select_first_selectable((target..len).chain((0..target).rev()));
+    }
+
+    pub fn select_first(&mut self) {
+        self.select_first_selectable(0..self.items.len());
+    }
+
+    pub fn select_last(&mut self) {
+        self.select_first_selectable((0..self.items.len()).rev());
+    }
+
+    fn selected_if_selectable(&self) -> Option<T> {
+        if self.is_selectable(self.selected_index) {
+            Some(self.selected().clone())
+        } else {
+            None
+        }
+    }
+
     pub fn index(&self) -> usize {
         self.selected_index
     }
@@ -153,6 +609,16 @@ impl<T: Clone> MenuInstance<T> {
         &self.items[self.selected_index]
     }
 
+    pub fn entry_to_render(&self, index: usize) -> MenuEntryToRender<'_, T> {
+        MenuEntryToRender {
+            entry: &self.items[index],
+            selected: index == self.selected_index,
+            index,
+            id: self.id_at(index),
+            kind: self.kind_at(index),
+        }
+    }
+
     pub fn enumerate(&self) -> impl Iterator<Item = (usize, &T, Option<Selected>)> {
         let selected_index = self.selected_index;
         self.items.iter().enumerate().map(move |(i, item)| {
@@ -165,26 +631,55 @@ impl<T: Clone> MenuInstance<T> {
         })
     }
 
+    /// Move the selection according to a navigation action. Choosing,
+    /// escaping and quitting don't change the selection.
+    pub fn perform<M>(&mut self, view: &M, action: MenuAction)
+    where
+        M: MenuIndexFromScreenCoord,
+    {
+        match action {
+            MenuAction::Up => self.up(),
+            MenuAction::Down => self.down(),
+            MenuAction::Left => self.left(),
+            MenuAction::Right => self.right(),
+            MenuAction::PageUp => self.page_up(view.menu_page_size().unwrap_or(self.items.len())),
+            MenuAction::PageDown => {
+                self.page_down(view.menu_page_size().unwrap_or(self.items.len()))
+            }
+            MenuAction::First => self.select_first(),
+            MenuAction::Last => self.select_last(),
+            MenuAction::Choose | MenuAction::Escape | MenuAction::Quit => (),
+        }
+    }
+
     pub fn choose<M>(&mut self, view: &M, input: Input) -> Option<T>
     where
         M: MenuIndexFromScreenCoord,
     {
-        match input {
-            Input::Keyboard(keys::RETURN) | Input::Keyboard(KeyboardInput::Char(' ')) => {
-                return Some(self.selected().clone());
+        match self.key_map.action(input) {
+            Some(MenuAction::Choose) => return self.selected_if_selectable(),
+            Some(action) => {
+                self.perform(view, action);
+                return None;
             }
-            Input::Keyboard(KeyboardInput::Up)
-            | Input::Mouse(MouseInput::MouseScroll {
+            None => (),
+        }
+        match input {
+            Input::Mouse(MouseInput::MouseScroll {
                 direction: ScrollDirection::Up,
                 ..
-            }) => self.up(),
-            Input::Keyboard(KeyboardInput::Down)
-            | Input::Mouse(MouseInput::MouseScroll {
+            }) => self.previous(),
+            Input::Mouse(MouseInput::MouseScroll {
                 direction: ScrollDirection::Down,
                 ..
-            }) => self.down(),
+            }) => self.next(),
             Input::Keyboard(KeyboardInput::Char(c)) => {
-                if let Some(item) = self.hotkeys.get(&c).cloned() {
+                if let Some(&index) = self.item_hotkeys.get(&c) {
+                    if self.is_selectable(index) {
+                        self.selected_index = index;
+                        return Some(self.selected().clone());
+                    }
+                } else if let Some(item) = self.hotkeys.get(&c).cloned() {
                     return Some(item);
                 }
             }
@@ -195,20 +690,10 @@ impl<T: Clone> MenuInstance<T> {
             }
             Input::Mouse(MouseInput::MousePress { coord, .. }) => {
                 if let Some(index) = view.menu_index_from_screen_coord(self.items.len(), coord) {
-                    self.set_index(index);
-                    return Some(self.selected().clone());
-                }
-            }
-            #[cfg(feature = "gamepad")]
-            Input::Gamepad(gamepad_input) => {
-                use chargrid_input::GamepadButton;
-                match gamepad_input.button {
-                    GamepadButton::DPadDown => self.down(),
-                    GamepadButton::DPadUp => self.up(),
-                    GamepadButton::Start | GamepadButton::South => {
-                        return Some(self.selected().clone())
+                    if self.is_selectable(index) {
+                        self.selected_index = index;
+                        return Some(self.selected().clone());
                     }
-                    _ => (),
                 }
             }
             _ => (),
@@ -220,14 +705,9 @@ impl<T: Clone> MenuInstance<T> {
     where
         M: MenuIndexFromScreenCoord,
     {
-        match input {
-            Input::Keyboard(keys::ESCAPE) => Some(Err(Escape)),
-            #[cfg(feature = "gamepad")]
-            Input::Gamepad(chargrid_input::GamepadInput {
-                button: chargrid_input::GamepadButton::East,
-                ..
-            }) => Some(Err(Escape)),
-            other => self.choose(view, other).map(Ok),
+        match self.key_map.action(input) {
+            Some(MenuAction::Escape) => Some(Err(Escape)),
+            _ => self.choose(view, input).map(Ok),
         }
     }
 
@@ -235,9 +715,9 @@ impl<T: Clone> MenuInstance<T> {
     where
         M: MenuIndexFromScreenCoord,
     {
-        match input {
-            Input::Keyboard(keys::ETX) => Some(Err(Quit)),
-            other => self.choose(view, other).map(Ok),
+        match self.key_map.action(input) {
+            Some(MenuAction::Quit) => Some(Err(Quit)),
+            _ => self.choose(view, input).map(Ok),
         }
     }
 
@@ -245,10 +725,10 @@ impl<T: Clone> MenuInstance<T> {
     where
         M: MenuIndexFromScreenCoord,
     {
-        match input {
-            Input::Keyboard(keys::ESCAPE) => Some(Err(Cancel::Escape)),
-            Input::Keyboard(keys::ETX) => Some(Err(Cancel::Quit)),
-            other => self.choose(view, other).map(Ok),
+        match self.key_map.action(input) {
+            Some(MenuAction::Escape) => Some(Err(Cancel::Escape)),
+            Some(MenuAction::Quit) => Some(Err(Cancel::Quit)),
+            _ => self.choose(view, input).map(Ok),
         }
     }
 
@@ -347,3 +827,171 @@ impl<T: Clone> MenuInstanceChoose for MenuInstanceChooseOrCancel<T> {
         &mut self.0
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct NoMouse;
+    impl MenuIndexFromScreenCoord for NoMouse {
+        fn menu_index_from_screen_coord(&self, _len: usize, _coord: Coord) -> Option<usize> {
+            None
+        }
+    }
+
+    #[test]
+    fn skips_unselectable_items() {
+        let mut menu_instance = MenuInstanceBuilder {
+            items: vec!["heading", "a", "b", "c"],
+            selected_index: 0,
+            hotkeys: Some(vec![('x', "c")].into_iter().collect()),
+        }
+        .build()
+        .unwrap()
+        .with_kinds(vec![MenuItemKind::Separator, MenuItemKind::Enabled])
+        .with_item_hotkey('c', 3);
+        assert_eq!(menu_instance.index(), 1);
+        menu_instance.set_enabled(2, false);
+        menu_instance.down();
+        assert_eq!(menu_instance.index(), 3);
+        menu_instance.down();
+        assert_eq!(menu_instance.index(), 1);
+        menu_instance.up();
+        assert_eq!(menu_instance.index(), 3);
+        menu_instance.set_enabled(3, false);
+        assert_eq!(menu_instance.index(), 1);
+        assert!(menu_instance
+            .choose(&NoMouse, Input::Keyboard(KeyboardInput::Char('c')))
+            .is_none());
+        assert_eq!(
+            menu_instance.choose(&NoMouse, Input::Keyboard(KeyboardInput::Char('x'))),
+            Some("c")
+        );
+        assert_eq!(menu_instance.kind(4), None);
+        assert_eq!(menu_instance.set_kind(4, MenuItemKind::Enabled), None);
+        assert_eq!(menu_instance.item_id(4), None);
+    }
+
+    #[test]
+    fn multi_row_entries() {
+        let mut mouse_tracker = MenuInstanceMouseTracker::default();
+        mouse_tracker.new_frame(Coord::new(5, 5));
+        mouse_tracker.on_entry_view_size(Size::new(4, 2));
+        mouse_tracker.on_entry_view_size(Size::new(0, 0));
+        mouse_tracker.on_entry_view_size(Size::new(2, 3));
+        let index = |x, y| mouse_tracker.menu_index_from_screen_coord(3, Coord::new(x, y));
+        assert_eq!(index(5, 5), Some(0));
+        assert_eq!(index(8, 6), Some(0));
+        assert_eq!(index(8, 7), Some(1));
+        assert_eq!(index(5, 10), Some(2));
+        assert_eq!(index(5, 11), None);
+        assert_eq!(index(9, 8), None);
+    }
+
+    #[test]
+    fn grid_navigation() {
+        let mut menu_instance =
+            MenuInstance::new((0..7).collect())
+                .unwrap()
+                .with_layout(MenuLayout::Grid {
+                    columns: 3,
+                    spacing: 1,
+                });
+        menu_instance.right();
+        menu_instance.down();
+        assert_eq!(menu_instance.index(), 4);
+        menu_instance.down();
+        assert_eq!(menu_instance.index(), 1);
+        menu_instance.up();
+        assert_eq!(menu_instance.index(), 4);
+        menu_instance.set_index(2);
+        menu_instance.down();
+        menu_instance.down();
+        assert_eq!(menu_instance.index(), 2);
+        menu_instance.set_wrap(false);
+        menu_instance.right();
+        menu_instance.right();
+        menu_instance.up();
+        assert_eq!(menu_instance.index(), 1);
+        menu_instance.set_index(6);
+        menu_instance.right();
+        assert_eq!(menu_instance.index(), 6);
+    }
+
+    #[test]
+    fn horizontal_navigation() {
+        let mut menu_instance = MenuInstance::new((0..4).collect())
+            .unwrap()
+            .with_layout(MenuLayout::Horizontal { spacing: 1 });
+        menu_instance.right();
+        assert_eq!(menu_instance.index(), 1);
+        menu_instance.down();
+        assert_eq!(menu_instance.index(), 3);
+        menu_instance.up();
+        assert_eq!(menu_instance.index(), 0);
+    }
+
+    #[test]
+    fn key_map() {
+        let mut menu_instance = MenuInstanceBuilder {
+            items: vec!["a", "b", "c"],
+            selected_index: 0,
+            hotkeys: Some(vec![('j', "c"), ('q', "b")].into_iter().collect()),
+        }
+        .build()
+        .unwrap()
+        .with_key_map(MenuKeyMap::vi().with_key(KeyboardInput::Char('q'), MenuAction::Escape));
+        let mut input = |c| menu_instance.choose_or_escape(&NoMouse, Input::Keyboard(c));
+        assert!(input(KeyboardInput::Char('j')).is_none());
+        assert!(input(KeyboardInput::Char('q')).unwrap().is_err());
+        assert!(input(KeyboardInput::End).is_none());
+        assert_eq!(input(KeyboardInput::Char(' ')).unwrap().ok(), Some("c"));
+    }
+
+    #[test]
+    fn replace_items() {
+        let mut menu_instance = MenuInstanceBuilder {
+            items: vec!["sword", "shield", "potion", "scroll"],
+            selected_index: 2,
+            hotkeys: None,
+        }
+        .build()
+        .unwrap()
+        .with_kinds(vec![MenuItemKind::Enabled, MenuItemKind::Disabled])
+        .with_item_hotkey('p', 2)
+        .with_item_hotkey('c', 3);
+        let potion_id = menu_instance.item_id(2);
+        menu_instance
+            .replace_items(vec!["bow", "shield", "potion"], |&item| item)
+            .unwrap();
+        assert_eq!(menu_instance.index(), 2);
+        assert_eq!(menu_instance.item_id(2), potion_id);
+        assert_eq!(menu_instance.kind(1), Some(MenuItemKind::Disabled));
+        assert_eq!(menu_instance.item_hotkeys.len(), 1);
+        menu_instance
+            .replace_items(vec!["bow", "shield"], |&item| item)
+            .unwrap();
+        assert_eq!(menu_instance.index(), 0);
+        assert!(menu_instance.replace_items(vec![], |&item| item).is_err());
+        assert_eq!(menu_instance.len(), 2);
+        assert_eq!(menu_instance.index(), 0);
+        let mut menu_instance = MenuInstanceBuilder {
+            items: vec!["a", "b", "c", "d", "e", "f"],
+            selected_index: 2,
+            hotkeys: None,
+        }
+        .build()
+        .unwrap()
+        .with_kinds(vec![
+            MenuItemKind::Enabled,
+            MenuItemKind::Enabled,
+            MenuItemKind::Enabled,
+            MenuItemKind::Disabled,
+            MenuItemKind::Disabled,
+        ]);
+        menu_instance
+            .replace_items(vec!["a", "b", "d", "e", "f"], |&item| item)
+            .unwrap();
+        assert_eq!(menu_instance.index(), 1);
+    }
+}
@@ -0,0 +1,136 @@
+#[cfg(feature = "gamepad")]
+use chargrid_input::GamepadButton;
+use chargrid_input::{keys, Input, KeyboardInput};
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MenuAction {
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    First,
+    Last,
+    Choose,
+    Escape,
+    Quit,
+}
+
+/// Map from inputs to the menu actions they perform. The default binds the
+/// arrow keys, PageUp, PageDown, Home and End to navigation, Return and Space
+/// to choosing the selected entry, and Escape and ETX (ctrl-c) to escaping
+/// and quitting, along with the d-pad, Start, South and East on gamepads.
+/// When deserializing, a missing set of bindings takes its default.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serialize", serde(default))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MenuKeyMap {
+    pub keyboard: HashMap<KeyboardInput, MenuAction>,
+    #[cfg(feature = "gamepad")]
+    pub gamepad: HashMap<GamepadButton, MenuAction>,
+}
+
+impl Default for MenuKeyMap {
+    fn default() -> Self {
+        let keyboard = vec![
+            (KeyboardInput::Up, MenuAction::Up),
+            (KeyboardInput::Down, MenuAction::Down),
+            (KeyboardInput::Left, MenuAction::Left),
+            (KeyboardInput::Right, MenuAction::Right),
+            (KeyboardInput::PageUp, MenuAction::PageUp),
+            (KeyboardInput::PageDown, MenuAction::PageDown),
+            (KeyboardInput::Home, MenuAction::First),
+            (KeyboardInput::End, MenuAction::Last),
+            (keys::RETURN, MenuAction::Choose),
+            (KeyboardInput::Char(' '), MenuAction::Choose),
+            (keys::ESCAPE, MenuAction::Escape),
+            (keys::ETX, MenuAction::Quit),
+        ]
+        .into_iter()
+        .collect();
+        #[cfg(feature = "gamepad")]
+        let gamepad = vec![
+            (GamepadButton::DPadUp, MenuAction::Up),
+            (GamepadButton::DPadDown, MenuAction::Down),
+            (GamepadButton::DPadLeft, MenuAction::Left),
+            (GamepadButton::DPadRight, MenuAction::Right),
+            (GamepadButton::LeftBumper, MenuAction::PageUp),
+            (GamepadButton::RightBumper, MenuAction::PageDown),
+            (GamepadButton::Start, MenuAction::Choose),
+            (GamepadButton::South, MenuAction::Choose),
+            (GamepadButton::East, MenuAction::Escape),
+        ]
+        .into_iter()
+        .collect();
+        Self {
+            keyboard,
+            #[cfg(feature = "gamepad")]
+            gamepad,
+        }
+    }
+}
+
+impl MenuKeyMap {
+    /// A key map with no bindings
+    pub fn empty() -> Self {
+        Self {
+            keyboard: HashMap::new(),
+            #[cfg(feature = "gamepad")]
+            gamepad: HashMap::new(),
+        }
+    }
+
+    /// The default key map, with h, j, k and l also bound to moving left,
+    /// down, up and right. This shadows hotkeys on those keys.
+    pub fn vi() -> Self {
+        Self::default()
+            .with_key(KeyboardInput::Char('h'), MenuAction::Left)
+            .with_key(KeyboardInput::Char('j'), MenuAction::Down)
+            .with_key(KeyboardInput::Char('k'), MenuAction::Up)
+            .with_key(KeyboardInput::Char('l'), MenuAction::Right)
+    }
+
+    pub fn with_key(mut self, keyboard_input: KeyboardInput, action: MenuAction) -> Self {
+        self.bind_key(keyboard_input, action);
+        self
+    }
+
+    pub fn bind_key(&mut self, keyboard_input: KeyboardInput, action: MenuAction) {
+        self.keyboard.insert(keyboard_input, action);
+    }
+
+    pub fn unbind_key(&mut self, keyboard_input: KeyboardInput) {
+        self.keyboard.remove(&keyboard_input);
+    }
+
+    #[cfg(feature = "gamepad")]
+    pub fn with_button(mut self, button: GamepadButton, action: MenuAction) -> Self {
+        self.bind_button(button, action);
+        self
+    }
+
+    #[cfg(feature = "gamepad")]
+    pub fn bind_button(&mut self, button: GamepadButton, action: MenuAction) {
+        self.gamepad.insert(button, action);
+    }
+
+    #[cfg(feature = "gamepad")]
+    pub fn unbind_button(&mut self, button: GamepadButton) {
+        self.gamepad.remove(&button);
+    }
+
+    /// The action bound to an input, if any. Mouse inputs are never bound.
+    pub fn action(&self, input: Input) -> Option<MenuAction> {
+        match input {
+            Input::Keyboard(keyboard_input) => self.keyboard.get(&keyboard_input).cloned(),
+            Input::Mouse(_) => None,
+            #[cfg(feature = "gamepad")]
+            Input::Gamepad(gamepad_input) => self.gamepad.get(&gamepad_input.button).cloned(),
+        }
+    }
+}
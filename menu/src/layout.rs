@@ -0,0 +1,96 @@
+use crate::scroll::MenuWindow;
+use crate::MenuInstanceMouseTracker;
+use chargrid_render::*;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+/// How the entries of a menu are arranged, which determines the arrow keys
+/// that move the selection and the direction in which the menu scrolls when
+/// it doesn't fit in its view.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuLayout {
+    /// Entries in a single column, navigated with Up and Down
+    Vertical,
+    /// Entries in a single row separated by `spacing` columns, such as a tab
+    /// or action bar, navigated with Left and Right. Up and Down select the
+    /// first and last entry.
+    Horizontal { spacing: u32 },
+    /// Entries in rows of `columns` entries, such as an item grid. Columns
+    /// are as wide as the widest entry, plus `spacing`.
+    Grid { columns: usize, spacing: u32 },
+}
+
+impl Default for MenuLayout {
+    fn default() -> Self {
+        MenuLayout::Vertical
+    }
+}
+
+impl MenuLayout {
+    /// The column containing the entry at an index
+    pub(crate) fn column(self, index: usize) -> usize {
+        match self {
+            MenuLayout::Vertical => 0,
+            MenuLayout::Horizontal { .. } => index,
+            MenuLayout::Grid { columns, .. } => index % columns.max(1),
+        }
+    }
+}
+
+/// The number of rows needed to arrange `len` entries in rows of `columns`
+/// entries
+// `usize::div_ceil` would require rust 1.73
+#[allow(clippy::manual_div_ceil)]
+pub(crate) fn num_rows(len: usize, columns: usize) -> usize {
+    (len + columns - 1) / columns
+}
+
+/// Draw the visible entries of a menu according to its layout, recording
+/// where each entry was drawn in the mouse tracker relative to the origin of
+/// the window. `entry_width` is only called for grids, to determine the
+/// column width before drawing.
+pub(crate) fn view_entries<C, W, D>(
+    layout: MenuLayout,
+    window: &MenuWindow,
+    context: ViewContext<C>,
+    mouse_tracker: &mut MenuInstanceMouseTracker,
+    entry_width: W,
+    mut view_entry: D,
+) where
+    C: ColModify,
+    W: FnMut(usize) -> u32,
+    D: FnMut(usize, ViewContext<C>) -> Size,
+{
+    match layout {
+        MenuLayout::Vertical => {
+            for i in window.indices.clone() {
+                let size = view_entry(i, window.entry_context(i, context));
+                mouse_tracker.on_entry_view_size(size);
+            }
+        }
+        MenuLayout::Horizontal { spacing } => {
+            let mut x = 0;
+            for i in window.indices.clone() {
+                let coord = Coord::new(x, 0);
+                let size = view_entry(i, context.add_offset(window.origin + coord));
+                let size = Size::new(size.width().max(1), 1);
+                mouse_tracker.on_entry_rect(i, coord, size);
+                x += (size.width() + spacing) as i32;
+            }
+        }
+        MenuLayout::Grid { columns, spacing } => {
+            let columns = columns.max(1);
+            let column_width = window.indices.clone().map(entry_width).max().unwrap_or(0) + spacing;
+            let first_row = window.indices.start / columns;
+            for i in window.indices.clone() {
+                let coord = Coord::new(
+                    ((i % columns) as u32 * column_width) as i32,
+                    (i / columns - first_row) as i32,
+                );
+                view_entry(i, context.add_offset(window.origin + coord));
+                mouse_tracker.on_entry_rect(i, coord, Size::new(column_width.max(1), 1));
+            }
+        }
+    }
+}
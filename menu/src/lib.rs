@@ -1,17 +1,41 @@
 mod event_routine;
 pub use event_routine::*;
 
+mod settings;
+pub use settings::*;
+
 mod static_style;
 pub use static_style::*;
 
 mod dynamic_style;
 pub use dynamic_style::*;
 
+mod entry_view;
+pub use entry_view::*;
+
 mod fade;
 pub use fade::*;
 
+mod filter;
+pub use filter::*;
+
 mod instance;
 pub use instance::*;
 
+mod key_map;
+pub use key_map::*;
+
+mod layout;
+pub use layout::MenuLayout;
+
+mod popup;
+pub use popup::*;
+
+mod scroll;
+pub use scroll::MenuScrollIndicators;
+
+mod submenu;
+pub use submenu::*;
+
 mod view;
 pub use view::*;
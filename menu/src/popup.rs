@@ -0,0 +1,354 @@
+use crate::{
+    ChooseSelector, Escape, MenuIndexFromScreenCoord, MenuInstanceChoose, MenuInstanceRoutine,
+};
+use chargrid_decorator::{BorderStyle, BorderView, FillBackgroundView};
+use chargrid_event_routine::{
+    event_or_peek_with_handled, Event, EventOrPeek, EventRoutine, Handled, ViewSelector,
+};
+use chargrid_input::{Input, MouseInput};
+use chargrid_render::*;
+
+/// Position of the top-left corner of a pop-up of a given size, such that
+/// its top-left corner is at the anchor if it fits within the bounds. If it
+/// would extend past the right or bottom of the bounds, it's flipped to the
+/// left of or above the anchor instead, and if it still doesn't fit it's
+/// moved as far left or up as the bounds allow.
+pub fn popup_top_left(anchor: Coord, size: Size, bounds: Size) -> Coord {
+    fn axis(anchor: i32, size: i32, bounds: i32) -> i32 {
+        let start = if anchor + size > bounds {
+            anchor + 1 - size
+        } else {
+            anchor
+        };
+        start.min(bounds - size).max(0)
+    }
+    Coord::new(
+        axis(anchor.x, size.width() as i32, bounds.width() as i32),
+        axis(anchor.y, size.height() as i32, bounds.height() as i32),
+    )
+}
+
+/// Renders a menu view inside a border at an anchor coordinate, such as the
+/// position of the cursor when a context menu was opened. The pop-up is
+/// flipped to stay within the bounds of the view context, and its background
+/// is filled so it covers whatever is drawn beneath it. It is drawn one level
+/// of depth above the view context.
+pub struct PopupMenuView<V> {
+    menu_view: V,
+    border_style: BorderStyle,
+    background: Rgb24,
+    anchor: Coord,
+    last_rect: Option<(Coord, Size)>,
+}
+
+impl<V> PopupMenuView<V> {
+    pub fn new(menu_view: V) -> Self {
+        Self {
+            menu_view,
+            border_style: BorderStyle::new(),
+            background: Rgb24::new(0, 0, 0),
+            anchor: Coord::new(0, 0),
+            last_rect: None,
+        }
+    }
+
+    pub fn with_border_style(self, border_style: BorderStyle) -> Self {
+        Self {
+            border_style,
+            ..self
+        }
+    }
+
+    pub fn with_background(self, background: Rgb24) -> Self {
+        Self { background, ..self }
+    }
+
+    pub fn menu_view(&self) -> &V {
+        &self.menu_view
+    }
+
+    pub fn menu_view_mut(&mut self) -> &mut V {
+        &mut self.menu_view
+    }
+
+    /// The anchor relative to the view context
+    pub fn anchor(&self) -> Coord {
+        self.anchor
+    }
+
+    pub fn set_anchor(&mut self, anchor: Coord) {
+        self.anchor = anchor;
+    }
+
+    /// Whether a screen coordinate is inside the pop-up, including its
+    /// border, as of the last time it was rendered. Always false if the
+    /// pop-up hasn't been rendered yet.
+    pub fn contains(&self, coord: Coord) -> bool {
+        matches!(self.last_rect, Some((top_left, size)) if (coord - top_left).is_valid(size))
+    }
+
+    /// Whether a screen coordinate is outside the pop-up as of the last time
+    /// it was rendered. Always false if the pop-up hasn't been rendered yet,
+    /// since it isn't known where the pop-up will appear.
+    pub fn is_outside(&self, coord: Coord) -> bool {
+        matches!(self.last_rect, Some((top_left, size)) if !(coord - top_left).is_valid(size))
+    }
+}
+
+impl<V, T> View<T> for PopupMenuView<V>
+where
+    V: View<T>,
+    T: Copy,
+{
+    fn view<F: Frame, C: ColModify>(&mut self, data: T, context: ViewContext<C>, frame: &mut F) {
+        let size = measure_size(
+            &mut BorderView {
+                view: &mut self.menu_view,
+                style: &self.border_style,
+            },
+            data,
+            context,
+        );
+        let top_left = popup_top_left(self.anchor, size, context.size);
+        self.last_rect = Some((context.offset + top_left, size));
+        FillBackgroundView {
+            view: BorderView {
+                view: &mut self.menu_view,
+                style: &self.border_style,
+            },
+            rgb24: self.background,
+        }
+        .view(
+            data,
+            context
+                .add_offset(top_left)
+                .constrain_size_to(size)
+                .add_depth(1),
+            frame,
+        );
+    }
+}
+
+impl<V> MenuIndexFromScreenCoord for PopupMenuView<V>
+where
+    V: MenuIndexFromScreenCoord,
+{
+    fn menu_index_from_screen_coord(&self, len: usize, coord: Coord) -> Option<usize> {
+        self.menu_view.menu_index_from_screen_coord(len, coord)
+    }
+    fn menu_page_size(&self) -> Option<usize> {
+        self.menu_view.menu_page_size()
+    }
+}
+
+/// Runs a menu in a `PopupMenuView` anchored at a coordinate relative to the
+/// view context, such as a right-click context menu. Clicking outside the
+/// pop-up closes it in the same way as escaping the menu, once it has been
+/// rendered. As with
+/// `MenuInstanceRoutine`, the selected view renders the selected data.
+pub struct PopupMenuRoutine<S> {
+    menu_instance_routine: MenuInstanceRoutine<S>,
+    anchor: Coord,
+}
+
+impl<S, V, T> PopupMenuRoutine<S>
+where
+    S: ViewSelector<ViewOutput = PopupMenuView<V>> + ChooseSelector,
+    S::ChooseOutput: MenuInstanceChoose<Output = Result<T, Escape>>,
+    V: MenuIndexFromScreenCoord,
+    for<'a> PopupMenuView<V>: View<&'a S::DataOutput>,
+{
+    pub fn new(s: S, anchor: Coord) -> Self {
+        Self {
+            menu_instance_routine: MenuInstanceRoutine::new(s),
+            anchor,
+        }
+    }
+}
+
+impl<S, V, T> EventRoutine for PopupMenuRoutine<S>
+where
+    S: ViewSelector<ViewOutput = PopupMenuView<V>> + ChooseSelector,
+    S::ChooseOutput: MenuInstanceChoose<Output = Result<T, Escape>>,
+    V: MenuIndexFromScreenCoord,
+    for<'a> PopupMenuView<V>: View<&'a S::DataOutput>,
+{
+    type Return = Result<T, Escape>;
+    type Data = S::DataInput;
+    type View = S::ViewInput;
+    type Event = Input;
+
+    fn handle<EP>(
+        self,
+        data: &mut Self::Data,
+        view: &Self::View,
+        event_or_peek: EP,
+    ) -> Handled<Self::Return, Self>
+    where
+        EP: EventOrPeek<Event = Self::Event>,
+    {
+        event_or_peek_with_handled(event_or_peek, self, |s, event| {
+            if let Input::Mouse(MouseInput::MousePress { coord, .. }) = event {
+                if s.menu_instance_routine.s.view(view).is_outside(coord) {
+                    return Handled::Return(Err(Escape));
+                }
+            }
+            let Self {
+                menu_instance_routine,
+                anchor,
+            } = s;
+            menu_instance_routine
+                .handle(data, view, Event::new(event))
+                .map_continue(|menu_instance_routine| Self {
+                    menu_instance_routine,
+                    anchor,
+                })
+        })
+    }
+
+    fn view<F, C>(
+        &self,
+        data: &Self::Data,
+        view: &mut Self::View,
+        context: ViewContext<C>,
+        frame: &mut F,
+    ) where
+        F: Frame,
+        C: ColModify,
+    {
+        self.menu_instance_routine
+            .s
+            .view_mut(view)
+            .set_anchor(self.anchor);
+        self.menu_instance_routine.view(data, view, context, frame);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        MenuInstance, MenuInstanceChoose, MenuInstanceChooseOrEscape, MenuInstanceMouseTracker,
+    };
+    use chargrid_event_routine::DataSelector;
+    use chargrid_input::MouseButton;
+    use chargrid_text::StringViewSingleLine;
+
+    type Data = MenuInstanceChooseOrEscape<&'static str>;
+
+    #[derive(Default)]
+    struct ListView(MenuInstanceMouseTracker);
+
+    impl<'a> View<&'a Data> for ListView {
+        fn view<F: Frame, C: ColModify>(
+            &mut self,
+            data: &'a Data,
+            context: ViewContext<C>,
+            frame: &mut F,
+        ) {
+            self.0.new_frame(context.offset);
+            for (i, &entry, _) in data.menu_instance().enumerate() {
+                let size = StringViewSingleLine::new(Style::new()).view_size(
+                    entry,
+                    context.add_offset(Coord::new(0, i as i32)),
+                    frame,
+                );
+                self.0.on_entry_view_size(size);
+            }
+        }
+    }
+
+    impl MenuIndexFromScreenCoord for ListView {
+        fn menu_index_from_screen_coord(&self, len: usize, coord: Coord) -> Option<usize> {
+            self.0.menu_index_from_screen_coord(len, coord)
+        }
+    }
+
+    struct Popup;
+
+    impl DataSelector for Popup {
+        type DataInput = Data;
+        type DataOutput = Data;
+        fn data<'a>(&self, input: &'a Data) -> &'a Data {
+            input
+        }
+        fn data_mut<'a>(&self, input: &'a mut Data) -> &'a mut Data {
+            input
+        }
+    }
+
+    impl ViewSelector for Popup {
+        type ViewInput = PopupMenuView<ListView>;
+        type ViewOutput = PopupMenuView<ListView>;
+        fn view<'a>(&self, input: &'a Self::ViewInput) -> &'a Self::ViewOutput {
+            input
+        }
+        fn view_mut<'a>(&self, input: &'a mut Self::ViewInput) -> &'a mut Self::ViewOutput {
+            input
+        }
+    }
+
+    impl ChooseSelector for Popup {
+        type ChooseOutput = Data;
+        fn choose_mut<'a>(&self, input: &'a mut Data) -> &'a mut Data {
+            input
+        }
+    }
+
+    fn press(
+        data: &mut Data,
+        view: &PopupMenuView<ListView>,
+        coord: Coord,
+    ) -> Option<Result<&'static str, Escape>> {
+        let input = Input::Mouse(MouseInput::MousePress {
+            button: MouseButton::Left,
+            coord,
+        });
+        match PopupMenuRoutine::new(Popup, Coord::new(2, 1)).handle(data, view, Event::new(input)) {
+            Handled::Return(result) => Some(result),
+            Handled::Continue(_) => None,
+        }
+    }
+
+    #[test]
+    fn clicks_inside_and_outside() {
+        let mut data = MenuInstance::new(vec!["copy", "paste"])
+            .unwrap()
+            .into_choose_or_escape();
+        let mut view = PopupMenuView::new(ListView::default());
+        assert!(press(&mut data, &view, Coord::new(0, 0)).is_none());
+        let mut buffer = Buffer::new(Size::new(20, 10));
+        PopupMenuRoutine::new(Popup, Coord::new(2, 1)).view(
+            &data,
+            &mut view,
+            ViewContext::default_with_size(buffer.size()),
+            &mut buffer,
+        );
+        assert!(matches!(
+            press(&mut data, &view, Coord::new(3, 3)),
+            Some(Ok("paste"))
+        ));
+        assert!(matches!(
+            press(&mut data, &view, Coord::new(0, 0)),
+            Some(Err(Escape))
+        ));
+    }
+
+    #[test]
+    fn popup_flips_to_fit() {
+        let bounds = Size::new(20, 10);
+        let size = Size::new(6, 4);
+        assert_eq!(
+            popup_top_left(Coord::new(2, 3), size, bounds),
+            Coord::new(2, 3)
+        );
+        assert_eq!(
+            popup_top_left(Coord::new(17, 8), size, bounds),
+            Coord::new(12, 5)
+        );
+        assert_eq!(
+            popup_top_left(Coord::new(3, 1), Size::new(6, 12), bounds),
+            Coord::new(3, 0)
+        );
+    }
+}
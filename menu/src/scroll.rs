@@ -0,0 +1,284 @@
+use crate::layout::num_rows;
+use crate::MenuLayout;
+use chargrid_render::*;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// Cells drawn before and after the visible entries of a menu which is too
+/// long for its view, when there are entries scrolled out of view in that
+/// direction. `up` and `down` are drawn above and below menus which scroll
+/// vertically, and `left` and `right` beside horizontal menus.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy)]
+pub struct MenuScrollIndicators {
+    pub up: ViewCell,
+    pub down: ViewCell,
+    pub left: ViewCell,
+    pub right: ViewCell,
+}
+
+impl MenuScrollIndicators {
+    pub fn new(style: Style) -> Self {
+        Self {
+            up: ViewCell::new().with_character('▲').with_style(style),
+            down: ViewCell::new().with_character('▼').with_style(style),
+            left: ViewCell::new().with_character('◀').with_style(style),
+            right: ViewCell::new().with_character('▶').with_style(style),
+        }
+    }
+}
+
+impl Default for MenuScrollIndicators {
+    fn default() -> Self {
+        Self::new(Style::new())
+    }
+}
+
+/// The entries of a menu visible in a view, and where they are drawn
+#[derive(Debug, Clone)]
+pub(crate) struct MenuWindow {
+    pub(crate) indices: Range<usize>,
+    /// Where the first visible entry is drawn relative to the view, leaving
+    /// room for scroll indicators
+    pub(crate) origin: Coord,
+    more_before: bool,
+    more_after: bool,
+    indicator_coords: Option<(Coord, Coord)>,
+    sideways: bool,
+}
+
+impl MenuWindow {
+    pub(crate) fn all(len: usize) -> Self {
+        Self {
+            indices: 0..len,
+            origin: Coord::new(0, 0),
+            more_before: false,
+            more_after: false,
+            indicator_coords: None,
+            sideways: false,
+        }
+    }
+
+    pub(crate) fn entry_context<C: ColModify>(
+        &self,
+        index: usize,
+        context: ViewContext<C>,
+    ) -> ViewContext<C> {
+        context.add_offset(self.origin + Coord::new(0, (index - self.indices.start) as i32))
+    }
+}
+
+/// Scroll position of a menu view, which is adjusted when the view is
+/// rendered to keep the selected entry visible
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MenuScroll {
+    offset: usize,
+    pub(crate) indicators: Option<MenuScrollIndicators>,
+}
+
+impl MenuScroll {
+    /// Vertical menus scroll by entries and grids scroll by rows. Horizontal
+    /// menus scroll sideways by entries, whose widths are given by
+    /// `entry_width`.
+    pub(crate) fn update_for_layout<W: FnMut(usize) -> u32>(
+        &mut self,
+        layout: MenuLayout,
+        len: usize,
+        selected_index: usize,
+        size: Size,
+        mut entry_width: W,
+    ) -> MenuWindow {
+        match layout {
+            MenuLayout::Vertical => self.update(len, selected_index, size.height()),
+            MenuLayout::Horizontal { spacing } => {
+                // each entry is followed by spacing, which isn't needed after
+                // the last entry
+                let window = self.update_with_entry_heights(
+                    len,
+                    selected_index,
+                    size.width() + spacing,
+                    |i| entry_width(i).max(1) + spacing,
+                );
+                MenuWindow {
+                    origin: Coord::new(window.origin.y, 0),
+                    indicator_coords: window
+                        .indicator_coords
+                        .map(|_| (Coord::new(0, 0), Coord::new(size.width() as i32 - 1, 0))),
+                    sideways: true,
+                    ..window
+                }
+            }
+            MenuLayout::Grid { columns, .. } => {
+                let columns = columns.max(1);
+                let window = self.update(
+                    num_rows(len, columns),
+                    selected_index / columns,
+                    size.height(),
+                );
+                MenuWindow {
+                    indices: window.indices.start * columns
+                        ..(window.indices.end * columns).min(len),
+                    ..window
+                }
+            }
+        }
+    }
+
+    pub(crate) fn update(&mut self, len: usize, selected_index: usize, height: u32) -> MenuWindow {
+        self.update_with_entry_heights(len, selected_index, height, |_| 1)
+    }
+    /// Like `update`, for menus whose entries may be taller than a single row.
+    /// Entries occupy at least one row. Only entries near the visible window
+    /// are passed to `entry_height`, so it may measure entries lazily.
+    pub(crate) fn update_with_entry_heights<H: FnMut(usize) -> u32>(
+        &mut self,
+        len: usize,
+        selected_index: usize,
+        height: u32,
+        mut entry_height: H,
+    ) -> MenuWindow {
+        let mut entry_height = |index| entry_height(index).max(1);
+        let mut total = 0;
+        if (0..len).all(|i| {
+            total += entry_height(i);
+            total <= height
+        }) {
+            self.offset = 0;
+            return MenuWindow::all(len);
+        }
+        let (available, row_offset) = if self.indicators.is_some() {
+            (height.saturating_sub(2).max(1), 1)
+        } else {
+            (height, 0)
+        };
+        self.offset = self.offset.min(selected_index);
+        // the first entry which can be visible along with the selection
+        let mut first = selected_index;
+        let mut used = entry_height(selected_index);
+        while first > self.offset {
+            let above = entry_height(first - 1);
+            if used + above > available {
+                break;
+            }
+            used += above;
+            first -= 1;
+        }
+        self.offset = first;
+        // avoid leaving empty rows below the last entry
+        let mut below = 0;
+        let fits_below = (self.offset..len).all(|i| {
+            below += entry_height(i);
+            below <= available
+        });
+        if fits_below {
+            while self.offset > 0 {
+                let above = entry_height(self.offset - 1);
+                if below + above > available {
+                    break;
+                }
+                below += above;
+                self.offset -= 1;
+            }
+        }
+        let mut end = self.offset + 1;
+        let mut used = entry_height(self.offset);
+        while end < len && used + entry_height(end) <= available {
+            used += entry_height(end);
+            end += 1;
+        }
+        MenuWindow {
+            indices: self.offset..end,
+            origin: Coord::new(0, row_offset),
+            more_before: self.offset > 0,
+            more_after: end < len,
+            indicator_coords: self
+                .indicators
+                .map(|_| (Coord::new(0, 0), Coord::new(0, height as i32 - 1))),
+            sideways: false,
+        }
+    }
+
+    pub(crate) fn render_indicators<F: Frame, C: ColModify>(
+        &self,
+        window: &MenuWindow,
+        context: ViewContext<C>,
+        frame: &mut F,
+    ) {
+        if let (Some(indicators), Some((before_coord, after_coord))) =
+            (self.indicators, window.indicator_coords)
+        {
+            let (before, after) = if window.sideways {
+                (indicators.left, indicators.right)
+            } else {
+                (indicators.up, indicators.down)
+            };
+            if window.more_before {
+                frame.set_cell_relative(before_coord, 0, before, context);
+            }
+            if window.more_after {
+                frame.set_cell_relative(after_coord, 0, after, context);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn keeps_selection_visible() {
+        let mut scroll = MenuScroll::default();
+        assert_eq!(scroll.update(3, 2, 5).indices, 0..3);
+        assert_eq!(scroll.update(10, 7, 4).indices, 4..8);
+        assert_eq!(scroll.update(10, 5, 4).indices, 4..8);
+        assert_eq!(scroll.update(10, 1, 4).indices, 1..5);
+        scroll.indicators = Some(MenuScrollIndicators::default());
+        let window = scroll.update(10, 9, 4);
+        assert_eq!(window.indices, 8..10);
+        assert_eq!(window.origin, Coord::new(0, 1));
+        scroll.indicators = None;
+        let heights = [1, 3, 2, 1, 2];
+        let window = scroll.update_with_entry_heights(5, 2, 4, |i| heights[i]);
+        assert_eq!(window.indices, 2..4);
+        let window = scroll.update_with_entry_heights(5, 4, 4, |i| heights[i]);
+        assert_eq!(window.indices, 3..5);
+    }
+
+    #[test]
+    fn scrolls_grids_and_horizontal_menus() {
+        let mut scroll = MenuScroll::default();
+        let grid = MenuLayout::Grid {
+            columns: 3,
+            spacing: 1,
+        };
+        let window = scroll.update_for_layout(grid, 11, 10, Size::new(10, 2), |_| 1);
+        assert_eq!(window.indices, 6..11);
+        let window = scroll.update_for_layout(grid, 11, 1, Size::new(10, 2), |_| 1);
+        assert_eq!(window.indices, 0..6);
+        let mut scroll = MenuScroll {
+            indicators: Some(MenuScrollIndicators::default()),
+            ..Default::default()
+        };
+        let horizontal = MenuLayout::Horizontal { spacing: 1 };
+        let window = scroll.update_for_layout(horizontal, 5, 4, Size::new(10, 1), |_| 3);
+        assert_eq!(window.indices, 3..5);
+        assert_eq!(window.origin, Coord::new(1, 0));
+        assert_eq!(window.indicator_coords.unwrap().1, Coord::new(9, 0));
+        let window = scroll.update_for_layout(horizontal, 3, 2, Size::new(11, 1), |_| 3);
+        assert_eq!(window.indices, 0..3);
+    }
+
+    #[test]
+    fn measures_entries_near_window() {
+        let mut scroll = MenuScroll::default();
+        let mut measured = Vec::new();
+        let window = scroll.update_with_entry_heights(1000, 500, 4, |i| {
+            measured.push(i);
+            2
+        });
+        assert_eq!(window.indices, 499..501);
+        assert!(measured.iter().all(|&i| i < 4 || (497..503).contains(&i)));
+    }
+}
@@ -0,0 +1,514 @@
+use crate::{
+    Cancel, Escape, MenuAction, MenuEntryString, MenuEntryToRender, MenuIndexFromScreenCoord,
+    MenuInstance, StaticStyleMenuInstanceModel, StaticStyleMenuInstanceView,
+};
+use chargrid_event_routine::{event_or_peek_with_handled, EventOrPeek, EventRoutine, Handled};
+use chargrid_input::Input;
+use chargrid_render::*;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// The state of an entry of a settings menu
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingControl {
+    /// An entry which finishes the menu when chosen, such as "Back"
+    Action,
+    /// Toggled by choosing the entry
+    Checkbox { checked: bool },
+    /// Choosing the entry checks it, and unchecks the other entries in the
+    /// same group
+    Radio { group: u32, checked: bool },
+    /// A value adjusted by Left and Right, drawn as a bar
+    Slider {
+        value: i32,
+        min: i32,
+        max: i32,
+        step: i32,
+    },
+    /// A value adjusted by Left and Right, drawn as a number
+    Spinner {
+        value: i32,
+        min: i32,
+        max: i32,
+        step: i32,
+    },
+}
+
+impl SettingControl {
+    fn adjust(&mut self, forward: bool) -> bool {
+        match self {
+            SettingControl::Slider {
+                value,
+                min,
+                max,
+                step,
+            }
+            | SettingControl::Spinner {
+                value,
+                min,
+                max,
+                step,
+            } => {
+                let delta = if forward { *step } else { -*step };
+                let new_value = value.saturating_add(delta).max(*min).min(*max);
+                let changed = new_value != *value;
+                *value = new_value;
+                changed
+            }
+            _ => false,
+        }
+    }
+
+    fn is_adjustable(&self) -> bool {
+        matches!(
+            self,
+            SettingControl::Slider { .. } | SettingControl::Spinner { .. }
+        )
+    }
+}
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct SettingEntry<T> {
+    pub entry: T,
+    pub control: SettingControl,
+}
+
+impl<T> SettingEntry<T> {
+    pub fn action(entry: T) -> Self {
+        Self {
+            entry,
+            control: SettingControl::Action,
+        }
+    }
+    pub fn checkbox(entry: T, checked: bool) -> Self {
+        Self {
+            entry,
+            control: SettingControl::Checkbox { checked },
+        }
+    }
+    pub fn radio(entry: T, group: u32, checked: bool) -> Self {
+        Self {
+            entry,
+            control: SettingControl::Radio { group, checked },
+        }
+    }
+    pub fn slider(entry: T, value: i32, min: i32, max: i32, step: i32) -> Self {
+        Self {
+            entry,
+            control: SettingControl::Slider {
+                value,
+                min,
+                max,
+                step,
+            },
+        }
+    }
+    pub fn spinner(entry: T, value: i32, min: i32, max: i32, step: i32) -> Self {
+        Self {
+            entry,
+            control: SettingControl::Spinner {
+                value,
+                min,
+                max,
+                step,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SettingsMenuEvent<T> {
+    /// The control of an entry changed. The menu remains open.
+    Changed {
+        index: usize,
+        entry: T,
+        control: SettingControl,
+    },
+    /// An action entry was chosen
+    Chosen(T),
+}
+
+/// A menu of settings which change state in place when chosen or adjusted,
+/// reporting each change rather than finishing the menu
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct SettingsMenuInstance<T: Clone> {
+    menu_instance: MenuInstance<SettingEntry<T>>,
+}
+
+impl<T: Clone> SettingsMenuInstance<T> {
+    pub fn new(menu_instance: MenuInstance<SettingEntry<T>>) -> Self {
+        Self { menu_instance }
+    }
+
+    pub fn menu_instance(&self) -> &MenuInstance<SettingEntry<T>> {
+        &self.menu_instance
+    }
+
+    pub fn menu_instance_mut(&mut self) -> &mut MenuInstance<SettingEntry<T>> {
+        &mut self.menu_instance
+    }
+
+    pub fn control(&self, index: usize) -> SettingControl {
+        self.menu_instance.items[index].control
+    }
+
+    /// Update the state of an entry, such as when the setting is changed
+    /// elsewhere
+    pub fn set_control(&mut self, index: usize, control: SettingControl) {
+        self.menu_instance.items[index].control = control;
+    }
+
+    fn changed(&self, index: usize) -> SettingsMenuEvent<T> {
+        let setting_entry = &self.menu_instance.items[index];
+        SettingsMenuEvent::Changed {
+            index,
+            entry: setting_entry.entry.clone(),
+            control: setting_entry.control,
+        }
+    }
+
+    fn activate(&mut self, index: usize) -> Option<SettingsMenuEvent<T>> {
+        match self.menu_instance.items[index].control {
+            SettingControl::Action => Some(SettingsMenuEvent::Chosen(
+                self.menu_instance.items[index].entry.clone(),
+            )),
+            SettingControl::Checkbox { checked } => {
+                self.menu_instance.items[index].control =
+                    SettingControl::Checkbox { checked: !checked };
+                Some(self.changed(index))
+            }
+            SettingControl::Radio { group, .. } => {
+                for (i, setting_entry) in self.menu_instance.items.iter_mut().enumerate() {
+                    if let SettingControl::Radio {
+                        group: other_group,
+                        checked,
+                    } = &mut setting_entry.control
+                    {
+                        if *other_group == group {
+                            *checked = i == index;
+                        }
+                    }
+                }
+                Some(self.changed(index))
+            }
+            SettingControl::Slider { .. } | SettingControl::Spinner { .. } => None,
+        }
+    }
+
+    fn adjust(&mut self, forward: bool) -> Option<SettingsMenuEvent<T>> {
+        let index = self.menu_instance.index();
+        if self.menu_instance.is_selectable(index)
+            && self.menu_instance.items[index].control.adjust(forward)
+        {
+            Some(self.changed(index))
+        } else {
+            None
+        }
+    }
+
+    pub fn choose<M>(&mut self, view: &M, input: Input) -> Option<SettingsMenuEvent<T>>
+    where
+        M: MenuIndexFromScreenCoord,
+    {
+        let adjustable = self.menu_instance.selected().control.is_adjustable();
+        match self.menu_instance.key_map().action(input) {
+            Some(MenuAction::Left) if adjustable => self.adjust(false),
+            Some(MenuAction::Right) if adjustable => self.adjust(true),
+            _ => {
+                self.menu_instance.choose(view, input)?;
+                self.activate(self.menu_instance.index())
+            }
+        }
+    }
+
+    pub fn choose_or_escape<M>(
+        &mut self,
+        view: &M,
+        input: Input,
+    ) -> Option<Result<SettingsMenuEvent<T>, Escape>>
+    where
+        M: MenuIndexFromScreenCoord,
+    {
+        match self.menu_instance.key_map().action(input) {
+            Some(MenuAction::Escape) => Some(Err(Escape)),
+            _ => self.choose(view, input).map(Ok),
+        }
+    }
+
+    pub fn choose_or_cancel<M>(
+        &mut self,
+        view: &M,
+        input: Input,
+    ) -> Option<Result<SettingsMenuEvent<T>, Cancel>>
+    where
+        M: MenuIndexFromScreenCoord,
+    {
+        match self.menu_instance.key_map().action(input) {
+            Some(MenuAction::Quit) => Some(Err(Cancel::Quit)),
+            _ => self
+                .choose_or_escape(view, input)
+                .map(|result| result.map_err(|Escape| Cancel::Escape)),
+        }
+    }
+}
+
+/// Renders entries of a settings menu with indicators of their state
+struct SettingEntryString<'a, S> {
+    menu_entry_string: &'a S,
+    slider_width: u32,
+}
+
+impl<'a, S> MenuEntryString for SettingEntryString<'a, S>
+where
+    S: MenuEntryString,
+{
+    type Entry = SettingEntry<S::Entry>;
+    fn render_string(&self, entry: MenuEntryToRender<Self::Entry>, buf: &mut String) {
+        match entry.entry.control {
+            SettingControl::Checkbox { checked } => {
+                buf.push_str(if checked { "[x] " } else { "[ ] " })
+            }
+            SettingControl::Radio { checked, .. } => {
+                buf.push_str(if checked { "(*) " } else { "( ) " })
+            }
+            _ => (),
+        }
+        self.menu_entry_string.render_string(
+            MenuEntryToRender {
+                entry: &entry.entry.entry,
+                selected: entry.selected,
+                index: entry.index,
+                id: entry.id,
+                kind: entry.kind,
+            },
+            buf,
+        );
+        match entry.entry.control {
+            SettingControl::Slider {
+                value, min, max, ..
+            } => {
+                let range = (max as i64 - min as i64).max(1);
+                let filled = ((value as i64 - min as i64) * self.slider_width as i64 / range)
+                    .max(0)
+                    .min(self.slider_width as i64) as u32;
+                buf.push_str(" [");
+                for i in 0..self.slider_width {
+                    buf.push(if i < filled { '=' } else { '-' });
+                }
+                write!(buf, "] {}", value).unwrap();
+            }
+            SettingControl::Spinner { value, .. } => {
+                write!(buf, " < {} >", value).unwrap();
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Renders a `SettingsMenuInstance` as a list of entries with indicators of
+/// their state, such as `[x]` for a checked checkbox
+pub struct SettingsMenuInstanceView {
+    menu_view: StaticStyleMenuInstanceView,
+    slider_width: u32,
+}
+
+impl SettingsMenuInstanceView {
+    pub fn new(normal: Style, selected: Style) -> Self {
+        Self {
+            menu_view: StaticStyleMenuInstanceView::new(normal, selected),
+            slider_width: 10,
+        }
+    }
+
+    /// The number of cells in the bars of sliders
+    pub fn with_slider_width(self, slider_width: u32) -> Self {
+        Self {
+            slider_width,
+            ..self
+        }
+    }
+
+    pub fn menu_view(&self) -> &StaticStyleMenuInstanceView {
+        &self.menu_view
+    }
+
+    pub fn menu_view_mut(&mut self) -> &mut StaticStyleMenuInstanceView {
+        &mut self.menu_view
+    }
+}
+
+pub struct SettingsMenuInstanceModel<'a, T, S>
+where
+    T: Clone,
+    S: MenuEntryString<Entry = T>,
+{
+    pub settings_menu_instance: &'a SettingsMenuInstance<T>,
+    pub menu_entry_string: &'a S,
+}
+
+impl<'a, T, S> View<SettingsMenuInstanceModel<'a, T, S>> for SettingsMenuInstanceView
+where
+    T: Clone,
+    S: MenuEntryString<Entry = T>,
+{
+    fn view<F: Frame, C: ColModify>(
+        &mut self,
+        SettingsMenuInstanceModel {
+            settings_menu_instance,
+            menu_entry_string,
+        }: SettingsMenuInstanceModel<'a, T, S>,
+        context: ViewContext<C>,
+        frame: &mut F,
+    ) {
+        self.menu_view.view(
+            StaticStyleMenuInstanceModel {
+                menu_instance: &settings_menu_instance.menu_instance,
+                menu_entry_string: &SettingEntryString {
+                    menu_entry_string,
+                    slider_width: self.slider_width,
+                },
+            },
+            context,
+            frame,
+        );
+    }
+}
+
+impl MenuIndexFromScreenCoord for SettingsMenuInstanceView {
+    fn menu_index_from_screen_coord(&self, len: usize, coord: Coord) -> Option<usize> {
+        self.menu_view.menu_index_from_screen_coord(len, coord)
+    }
+    fn menu_page_size(&self) -> Option<usize> {
+        self.menu_view.menu_page_size()
+    }
+}
+
+/// Runs a settings menu until a setting changes, an action is chosen, or the
+/// menu is cancelled. The state of the menu is kept in its data, so it can be
+/// run again after handling a change.
+#[derive(Clone)]
+pub struct SettingsMenuInstanceRoutine<S> {
+    menu_entry_string: S,
+}
+
+impl<S> SettingsMenuInstanceRoutine<S>
+where
+    S: MenuEntryString,
+    S::Entry: Clone,
+{
+    pub fn new(menu_entry_string: S) -> Self {
+        Self { menu_entry_string }
+    }
+}
+
+impl<S> EventRoutine for SettingsMenuInstanceRoutine<S>
+where
+    S: MenuEntryString,
+    S::Entry: Clone,
+{
+    type Return = Result<SettingsMenuEvent<S::Entry>, Cancel>;
+    type Data = SettingsMenuInstance<S::Entry>;
+    type View = SettingsMenuInstanceView;
+    type Event = Input;
+
+    fn handle<EP>(
+        self,
+        data: &mut Self::Data,
+        view: &Self::View,
+        event_or_peek: EP,
+    ) -> Handled<Self::Return, Self>
+    where
+        EP: EventOrPeek<Event = Self::Event>,
+    {
+        event_or_peek_with_handled(event_or_peek, self, |s, event| {
+            if let Some(menu_output) = data.choose_or_cancel(view, event) {
+                Handled::Return(menu_output)
+            } else {
+                Handled::Continue(s)
+            }
+        })
+    }
+
+    fn view<F, CM>(
+        &self,
+        data: &Self::Data,
+        view: &mut Self::View,
+        context: ViewContext<CM>,
+        frame: &mut F,
+    ) where
+        F: Frame,
+        CM: ColModify,
+    {
+        let model = SettingsMenuInstanceModel {
+            settings_menu_instance: data,
+            menu_entry_string: &self.menu_entry_string,
+        };
+        view.view(model, context, frame);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chargrid_input::{keys, KeyboardInput};
+
+    struct NoMouse;
+    impl MenuIndexFromScreenCoord for NoMouse {
+        fn menu_index_from_screen_coord(&self, _len: usize, _coord: Coord) -> Option<usize> {
+            None
+        }
+    }
+
+    #[test]
+    fn controls() {
+        let mut settings = SettingsMenuInstance::new(
+            MenuInstance::new(vec![
+                SettingEntry::checkbox("Fullscreen", false),
+                SettingEntry::radio("Dark", 0, true),
+                SettingEntry::radio("Light", 0, false),
+                SettingEntry::slider("Volume", 9, 0, 10, 2),
+                SettingEntry::action("Back"),
+            ])
+            .unwrap(),
+        );
+        let mut input = |input| settings.choose(&NoMouse, Input::Keyboard(input));
+        assert!(matches!(
+            input(keys::RETURN),
+            Some(SettingsMenuEvent::Changed {
+                control: SettingControl::Checkbox { checked: true },
+                ..
+            })
+        ));
+        input(KeyboardInput::Down);
+        input(KeyboardInput::Down);
+        input(KeyboardInput::Char(' '));
+        input(KeyboardInput::Down);
+        assert!(input(KeyboardInput::Right).is_some());
+        assert!(input(KeyboardInput::Right).is_none());
+        input(KeyboardInput::Down);
+        assert!(matches!(
+            input(keys::RETURN),
+            Some(SettingsMenuEvent::Chosen("Back"))
+        ));
+        assert_eq!(
+            settings.control(1),
+            SettingControl::Radio {
+                group: 0,
+                checked: false
+            }
+        );
+        assert_eq!(
+            settings.control(3),
+            SettingControl::Slider {
+                value: 10,
+                min: 0,
+                max: 10,
+                step: 2
+            }
+        );
+    }
+}
@@ -1,6 +1,8 @@
+use crate::layout::view_entries;
+use crate::scroll::MenuScroll;
 use crate::{
-    MenuEntryString, MenuEntryToRender, MenuIndexFromScreenCoord, MenuInstance, MenuInstanceChoose,
-    MenuInstanceMouseTracker, Selected,
+    MenuEntryString, MenuIndexFromScreenCoord, MenuInstance, MenuInstanceChoose,
+    MenuInstanceMouseTracker, MenuItemKind, MenuScrollIndicators,
 };
 use chargrid_event_routine::{event_or_peek_with_handled, EventOrPeek, EventRoutine, Handled};
 use chargrid_input::Input;
@@ -13,6 +15,8 @@ pub struct StaticStyleMenuInstanceView {
     buf: String,
     selected: Style,
     normal: Style,
+    disabled: Style,
+    scroll: MenuScroll,
 }
 
 impl StaticStyleMenuInstanceView {
@@ -20,10 +24,22 @@ impl StaticStyleMenuInstanceView {
         Self {
             normal,
             selected,
+            disabled: normal,
             buf: String::new(),
             mouse_tracker: Default::default(),
+            scroll: Default::default(),
         }
     }
+
+    /// Style of disabled entries, which defaults to the normal style
+    pub fn with_disabled_style(self, disabled: Style) -> Self {
+        Self { disabled, ..self }
+    }
+
+    pub fn with_scroll_indicators(mut self, scroll_indicators: MenuScrollIndicators) -> Self {
+        self.scroll.indicators = Some(scroll_indicators);
+        self
+    }
 }
 
 pub struct StaticStyleMenuInstanceModel<'a, E, S>
@@ -31,8 +47,8 @@ where
     E: Clone,
     S: MenuEntryString<Entry = E>,
 {
-    menu_instance: &'a MenuInstance<E>,
-    menu_entry_string: &'a S,
+    pub(crate) menu_instance: &'a MenuInstance<E>,
+    pub(crate) menu_entry_string: &'a S,
 }
 
 impl<'a, E, S> View<StaticStyleMenuInstanceModel<'a, E, S>> for StaticStyleMenuInstanceView
@@ -49,28 +65,51 @@ where
         context: ViewContext<C>,
         frame: &mut F,
     ) {
-        self.mouse_tracker.new_frame(context.offset);
-        for (i, entry, maybe_selected) in menu_instance.enumerate() {
-            self.buf.clear();
-            let entry_to_render = MenuEntryToRender {
-                index: i,
-                entry,
-                selected: maybe_selected.is_some(),
-            };
-            menu_entry_string.render_string(entry_to_render, &mut self.buf);
-            let style = if let Some(Selected) = maybe_selected {
-                self.selected
-            } else {
-                self.normal
-            };
-            let mut view = StringViewSingleLine::new(style);
-            let size = view.view_size(
-                &self.buf,
-                context.add_offset(Coord::new(0, i as i32)),
-                frame,
-            );
-            self.mouse_tracker.on_entry_view_size(size);
-        }
+        let mut width_buf = String::new();
+        let mut entry_width = |i: usize| {
+            width_buf.clear();
+            menu_entry_string.render_string(menu_instance.entry_to_render(i), &mut width_buf);
+            width_buf.chars().count() as u32
+        };
+        let window = self.scroll.update_for_layout(
+            menu_instance.layout(),
+            menu_instance.len(),
+            menu_instance.index(),
+            context.size,
+            &mut entry_width,
+        );
+        self.mouse_tracker.new_frame(context.offset + window.origin);
+        self.mouse_tracker
+            .set_scroll_window(window.indices.start, window.indices.len());
+        let Self {
+            mouse_tracker,
+            buf,
+            selected,
+            normal,
+            disabled,
+            ..
+        } = self;
+        view_entries(
+            menu_instance.layout(),
+            &window,
+            context,
+            mouse_tracker,
+            &mut entry_width,
+            |i, entry_context| {
+                buf.clear();
+                let entry_to_render = menu_instance.entry_to_render(i);
+                menu_entry_string.render_string(entry_to_render, buf);
+                let style = if entry_to_render.selected {
+                    *selected
+                } else if entry_to_render.kind == MenuItemKind::Disabled {
+                    *disabled
+                } else {
+                    *normal
+                };
+                StringViewSingleLine::new(style).view_size(&*buf, entry_context, frame)
+            },
+        );
+        self.scroll.render_indicators(&window, context, frame);
     }
 }
 
@@ -78,6 +117,9 @@ impl MenuIndexFromScreenCoord for StaticStyleMenuInstanceView {
     fn menu_index_from_screen_coord(&self, len: usize, coord: Coord) -> Option<usize> {
         self.mouse_tracker.menu_index_from_screen_coord(len, coord)
     }
+    fn menu_page_size(&self) -> Option<usize> {
+        self.mouse_tracker.page_size()
+    }
 }
 
 pub struct StaticStyleMenuInstanceRoutine<C, S> {
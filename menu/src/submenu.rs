@@ -0,0 +1,474 @@
+use crate::{
+    Cancel, Escape, MenuAction, MenuEntryString, MenuEntryToRender, MenuIndexFromScreenCoord,
+    MenuInstance, MenuLayout, StaticStyleMenuInstanceModel, StaticStyleMenuInstanceView,
+};
+use chargrid_event_routine::{event_or_peek_with_handled, EventOrPeek, EventRoutine, Handled};
+use chargrid_input::Input;
+use chargrid_render::*;
+use chargrid_text::StringViewSingleLine;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+/// An entry of a hierarchical menu, which opens a child menu when chosen if
+/// it has one
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct SubmenuEntry<T: Clone> {
+    pub entry: T,
+    pub submenu: Option<MenuInstance<SubmenuEntry<T>>>,
+}
+
+impl<T: Clone> SubmenuEntry<T> {
+    pub fn leaf(entry: T) -> Self {
+        Self {
+            entry,
+            submenu: None,
+        }
+    }
+    pub fn submenu(entry: T, submenu: MenuInstance<SubmenuEntry<T>>) -> Self {
+        Self {
+            entry,
+            submenu: Some(submenu),
+        }
+    }
+}
+
+/// A hierarchy of menus, of which the most recently opened submenu receives
+/// input. Choosing an entry with a submenu opens it, and Escape returns to its
+/// parent. In vertical menus, Right also opens the submenu of the selected
+/// entry. Left returns to the parent from vertical menus, and from the first
+/// column of other menus. Otherwise Left and Right move the selection.
+/// Submenus remember their selection when closed.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct MenuStack<T: Clone> {
+    root: MenuInstance<SubmenuEntry<T>>,
+    /// Indices of the entries whose submenus are open, starting from the root
+    path: Vec<usize>,
+}
+
+impl<T: Clone> MenuStack<T> {
+    pub fn new(root: MenuInstance<SubmenuEntry<T>>) -> Self {
+        Self {
+            root,
+            path: Vec::new(),
+        }
+    }
+
+    /// The number of open submenus
+    pub fn depth(&self) -> usize {
+        self.path.len()
+    }
+
+    pub fn current(&self) -> &MenuInstance<SubmenuEntry<T>> {
+        let mut menu_instance = &self.root;
+        for &index in &self.path {
+            menu_instance = menu_instance.items[index]
+                .submenu
+                .as_ref()
+                .expect("path only contains entries with submenus");
+        }
+        menu_instance
+    }
+
+    pub fn current_mut(&mut self) -> &mut MenuInstance<SubmenuEntry<T>> {
+        let mut menu_instance = &mut self.root;
+        for &index in &self.path {
+            menu_instance = menu_instance.items[index]
+                .submenu
+                .as_mut()
+                .expect("path only contains entries with submenus");
+        }
+        menu_instance
+    }
+
+    /// The entries whose submenus are open, starting from the root
+    pub fn breadcrumbs(&self) -> Vec<&T> {
+        let mut breadcrumbs = Vec::with_capacity(self.path.len());
+        let mut menu_instance = &self.root;
+        for &index in &self.path {
+            let submenu_entry = &menu_instance.items[index];
+            breadcrumbs.push(&submenu_entry.entry);
+            if let Some(submenu) = submenu_entry.submenu.as_ref() {
+                menu_instance = submenu;
+            }
+        }
+        breadcrumbs
+    }
+
+    /// Open the submenu of the selected entry. Returns false if it has none.
+    pub fn descend(&mut self) -> bool {
+        let current = self.current();
+        let index = current.index();
+        if current.is_selectable(index) && current.selected().submenu.is_some() {
+            self.path.push(index);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Close the current submenu. Returns false if no submenu is open.
+    pub fn ascend(&mut self) -> bool {
+        self.path.pop().is_some()
+    }
+
+    /// Close all submenus
+    pub fn reset(&mut self) {
+        self.path.clear();
+    }
+
+    fn choose_path<M>(&mut self, view: &M, input: Input) -> Option<Vec<T>>
+    where
+        M: MenuIndexFromScreenCoord,
+    {
+        match self.current().key_map().action(input) {
+            Some(MenuAction::Right) => {
+                if self.current().layout() == MenuLayout::Vertical {
+                    self.descend();
+                } else {
+                    self.current_mut().right();
+                }
+            }
+            Some(MenuAction::Left) => {
+                let current = self.current();
+                if current.layout().column(current.index()) != 0 || !self.ascend() {
+                    self.current_mut().left();
+                }
+            }
+            _ => {
+                if self.current_mut().choose(view, input).is_some() && !self.descend() {
+                    let mut path = self.breadcrumbs().into_iter().cloned().collect::<Vec<_>>();
+                    path.push(self.current().selected().entry.clone());
+                    return Some(path);
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns the entries leading to the chosen entry, followed by the
+    /// chosen entry itself. Escape closes the current submenu, or escapes the
+    /// menu if no submenu is open.
+    pub fn choose_or_escape<M>(&mut self, view: &M, input: Input) -> Option<Result<Vec<T>, Escape>>
+    where
+        M: MenuIndexFromScreenCoord,
+    {
+        match self.current().key_map().action(input) {
+            Some(MenuAction::Escape) => {
+                if self.ascend() {
+                    None
+                } else {
+                    Some(Err(Escape))
+                }
+            }
+            _ => self.choose_path(view, input).map(Ok),
+        }
+    }
+
+    pub fn choose_or_cancel<M>(&mut self, view: &M, input: Input) -> Option<Result<Vec<T>, Cancel>>
+    where
+        M: MenuIndexFromScreenCoord,
+    {
+        match self.current().key_map().action(input) {
+            Some(MenuAction::Quit) => Some(Err(Cancel::Quit)),
+            _ => self
+                .choose_or_escape(view, input)
+                .map(|result| result.map_err(|Escape| Cancel::Escape)),
+        }
+    }
+}
+
+/// Renders the entries of a hierarchical menu, marking those with submenus
+struct SubmenuEntryString<'a, S> {
+    menu_entry_string: &'a S,
+    submenu_suffix: &'a str,
+}
+
+impl<'a, S> MenuEntryString for SubmenuEntryString<'a, S>
+where
+    S: MenuEntryString,
+    S::Entry: Clone,
+{
+    type Entry = SubmenuEntry<S::Entry>;
+    fn render_string(&self, entry: MenuEntryToRender<Self::Entry>, buf: &mut String) {
+        self.menu_entry_string.render_string(
+            MenuEntryToRender {
+                entry: &entry.entry.entry,
+                selected: entry.selected,
+                index: entry.index,
+                id: entry.id,
+                kind: entry.kind,
+            },
+            buf,
+        );
+        if entry.entry.submenu.is_some() {
+            buf.push_str(self.submenu_suffix);
+        }
+    }
+}
+
+/// Renders the current menu of a `MenuStack`, below the breadcrumbs of its
+/// open submenus if there are any
+pub struct MenuStackView {
+    menu_view: StaticStyleMenuInstanceView,
+    breadcrumb_style: Style,
+    breadcrumb_separator: String,
+    submenu_suffix: String,
+    buf: String,
+}
+
+impl MenuStackView {
+    pub fn new(normal: Style, selected: Style) -> Self {
+        Self {
+            menu_view: StaticStyleMenuInstanceView::new(normal, selected),
+            breadcrumb_style: normal,
+            breadcrumb_separator: " > ".to_string(),
+            submenu_suffix: " >".to_string(),
+            buf: String::new(),
+        }
+    }
+
+    pub fn with_breadcrumb_style(self, breadcrumb_style: Style) -> Self {
+        Self {
+            breadcrumb_style,
+            ..self
+        }
+    }
+
+    pub fn with_breadcrumb_separator(self, breadcrumb_separator: &str) -> Self {
+        Self {
+            breadcrumb_separator: breadcrumb_separator.to_string(),
+            ..self
+        }
+    }
+
+    /// Text appended to entries which open a submenu
+    pub fn with_submenu_suffix(self, submenu_suffix: &str) -> Self {
+        Self {
+            submenu_suffix: submenu_suffix.to_string(),
+            ..self
+        }
+    }
+
+    pub fn menu_view(&self) -> &StaticStyleMenuInstanceView {
+        &self.menu_view
+    }
+
+    pub fn menu_view_mut(&mut self) -> &mut StaticStyleMenuInstanceView {
+        &mut self.menu_view
+    }
+}
+
+pub struct MenuStackModel<'a, T, S>
+where
+    T: Clone,
+    S: MenuEntryString<Entry = T>,
+{
+    pub menu_stack: &'a MenuStack<T>,
+    pub menu_entry_string: &'a S,
+}
+
+impl<'a, T, S> View<MenuStackModel<'a, T, S>> for MenuStackView
+where
+    T: Clone,
+    S: MenuEntryString<Entry = T>,
+{
+    fn view<F: Frame, C: ColModify>(
+        &mut self,
+        MenuStackModel {
+            menu_stack,
+            menu_entry_string,
+        }: MenuStackModel<'a, T, S>,
+        context: ViewContext<C>,
+        frame: &mut F,
+    ) {
+        let menu_context = if menu_stack.depth() == 0 {
+            context
+        } else {
+            self.buf.clear();
+            let mut menu_instance = &menu_stack.root;
+            for (i, &index) in menu_stack.path.iter().enumerate() {
+                if i > 0 {
+                    self.buf.push_str(&self.breadcrumb_separator);
+                }
+                menu_entry_string.render_string(
+                    MenuEntryToRender {
+                        entry: &menu_instance.items[index].entry,
+                        selected: false,
+                        index,
+                        id: menu_instance.id_at(index),
+                        kind: menu_instance.kind_at(index),
+                    },
+                    &mut self.buf,
+                );
+                if let Some(submenu) = menu_instance.items[index].submenu.as_ref() {
+                    menu_instance = submenu;
+                }
+            }
+            StringViewSingleLine::new(self.breadcrumb_style).view(&self.buf, context, frame);
+            context.add_offset(Coord::new(0, 1))
+        };
+        self.menu_view.view(
+            StaticStyleMenuInstanceModel {
+                menu_instance: menu_stack.current(),
+                menu_entry_string: &SubmenuEntryString {
+                    menu_entry_string,
+                    submenu_suffix: &self.submenu_suffix,
+                },
+            },
+            menu_context,
+            frame,
+        );
+    }
+}
+
+impl MenuIndexFromScreenCoord for MenuStackView {
+    fn menu_index_from_screen_coord(&self, len: usize, coord: Coord) -> Option<usize> {
+        self.menu_view.menu_index_from_screen_coord(len, coord)
+    }
+    fn menu_page_size(&self) -> Option<usize> {
+        self.menu_view.menu_page_size()
+    }
+}
+
+/// Runs a hierarchical menu until an entry without a submenu is chosen,
+/// returning the entries leading to it followed by the entry itself
+#[derive(Clone)]
+pub struct MenuStackRoutine<S> {
+    menu_entry_string: S,
+}
+
+impl<S> MenuStackRoutine<S>
+where
+    S: MenuEntryString,
+    S::Entry: Clone,
+{
+    pub fn new(menu_entry_string: S) -> Self {
+        Self { menu_entry_string }
+    }
+}
+
+impl<S> EventRoutine for MenuStackRoutine<S>
+where
+    S: MenuEntryString,
+    S::Entry: Clone,
+{
+    type Return = Result<Vec<S::Entry>, Cancel>;
+    type Data = MenuStack<S::Entry>;
+    type View = MenuStackView;
+    type Event = Input;
+
+    fn handle<EP>(
+        self,
+        data: &mut Self::Data,
+        view: &Self::View,
+        event_or_peek: EP,
+    ) -> Handled<Self::Return, Self>
+    where
+        EP: EventOrPeek<Event = Self::Event>,
+    {
+        event_or_peek_with_handled(event_or_peek, self, |s, event| {
+            if let Some(menu_output) = data.choose_or_cancel(view, event) {
+                Handled::Return(menu_output)
+            } else {
+                Handled::Continue(s)
+            }
+        })
+    }
+
+    fn view<F, CM>(
+        &self,
+        data: &Self::Data,
+        view: &mut Self::View,
+        context: ViewContext<CM>,
+        frame: &mut F,
+    ) where
+        F: Frame,
+        CM: ColModify,
+    {
+        let model = MenuStackModel {
+            menu_stack: data,
+            menu_entry_string: &self.menu_entry_string,
+        };
+        view.view(model, context, frame);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chargrid_input::{keys, KeyboardInput};
+
+    struct NoMouse;
+    impl MenuIndexFromScreenCoord for NoMouse {
+        fn menu_index_from_screen_coord(&self, _len: usize, _coord: Coord) -> Option<usize> {
+            None
+        }
+    }
+
+    #[test]
+    fn path_of_choices() {
+        let graphics = MenuInstance::new(vec![
+            SubmenuEntry::leaf("Fullscreen"),
+            SubmenuEntry::submenu(
+                "Palette",
+                MenuInstance::new(vec![
+                    SubmenuEntry::leaf("Dark"),
+                    SubmenuEntry::leaf("Light"),
+                ])
+                .unwrap(),
+            ),
+        ])
+        .unwrap();
+        let root = MenuInstance::new(vec![
+            SubmenuEntry::submenu("Graphics", graphics),
+            SubmenuEntry::leaf("Back"),
+        ])
+        .unwrap();
+        let mut menu_stack = MenuStack::new(root);
+        let input = |menu_stack: &mut MenuStack<&'static str>, input| {
+            menu_stack.choose_or_cancel(&NoMouse, Input::Keyboard(input))
+        };
+        assert!(input(&mut menu_stack, KeyboardInput::Right).is_none());
+        assert!(input(&mut menu_stack, KeyboardInput::Down).is_none());
+        assert!(input(&mut menu_stack, keys::RETURN).is_none());
+        assert_eq!(menu_stack.breadcrumbs(), vec![&"Graphics", &"Palette"]);
+        assert!(input(&mut menu_stack, keys::ESCAPE).is_none());
+        assert!(input(&mut menu_stack, KeyboardInput::Right).is_none());
+        assert!(input(&mut menu_stack, KeyboardInput::Down).is_none());
+        assert_eq!(
+            input(&mut menu_stack, keys::RETURN).unwrap().unwrap(),
+            vec!["Graphics", "Palette", "Light"]
+        );
+        menu_stack.reset();
+        assert!(matches!(
+            input(&mut menu_stack, keys::ESCAPE),
+            Some(Err(Cancel::Escape))
+        ));
+    }
+
+    #[test]
+    fn left_in_horizontal_submenu() {
+        let tabs = MenuInstance::new(vec![
+            SubmenuEntry::leaf("Audio"),
+            SubmenuEntry::leaf("Video"),
+        ])
+        .unwrap()
+        .with_layout(MenuLayout::Horizontal { spacing: 1 });
+        let root = MenuInstance::new(vec![SubmenuEntry::submenu("Settings", tabs)]).unwrap();
+        let mut menu_stack = MenuStack::new(root);
+        let input = |menu_stack: &mut MenuStack<&'static str>, input| {
+            menu_stack.choose_or_cancel(&NoMouse, Input::Keyboard(input))
+        };
+        assert!(input(&mut menu_stack, KeyboardInput::Right).is_none());
+        assert!(input(&mut menu_stack, KeyboardInput::Right).is_none());
+        assert_eq!(menu_stack.depth(), 1);
+        assert_eq!(menu_stack.current().index(), 1);
+        assert!(input(&mut menu_stack, KeyboardInput::Left).is_none());
+        assert_eq!(menu_stack.depth(), 1);
+        assert_eq!(menu_stack.current().index(), 0);
+        assert!(input(&mut menu_stack, KeyboardInput::Left).is_none());
+        assert_eq!(menu_stack.depth(), 0);
+    }
+}
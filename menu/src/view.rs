@@ -1,12 +1,22 @@
-use chargrid_render::Style;
+use crate::{MenuItemId, MenuItemKind};
+use chargrid_render::{ColModify, Frame, Style, ViewContext};
 use std::marker::PhantomData;
 
 pub struct MenuEntryToRender<'a, E> {
     pub entry: &'a E,
     pub selected: bool,
     pub index: usize,
+    pub id: MenuItemId,
+    pub kind: MenuItemKind,
 }
 
+impl<'a, E> Clone for MenuEntryToRender<'a, E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<'a, E> Copy for MenuEntryToRender<'a, E> {}
+
 pub trait MenuEntryString {
     type Entry;
     fn render_string(&self, entry: MenuEntryToRender<Self::Entry>, buf: &mut String);
@@ -77,3 +87,15 @@ where
         (self.f)(entry, buf)
     }
 }
+
+/// Renders menu entries as arbitrary views, such as an item name with a
+/// description below it. Entries may occupy several rows.
+pub trait MenuEntryView {
+    type Entry;
+    fn view_entry<F: Frame, C: ColModify>(
+        &self,
+        entry: MenuEntryToRender<Self::Entry>,
+        context: ViewContext<C>,
+        frame: &mut F,
+    );
+}
@@ -0,0 +1,254 @@
+use crate::wrap::{justified_gap_widths, Wrap};
+use chargrid_render::*;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextAlignment {
+    Left,
+    Centre,
+    Right,
+    /// Widen the gaps between words so that each line fills the available
+    /// width, except for lines ending in a newline and the final line, which
+    /// are left-aligned. Spaces before the first word of a line aren't
+    /// widened. Lines are justified in the same way as by `wrap::Justify`,
+    /// so using both is equivalent to using `wrap::Justify` alone.
+    Justify,
+}
+
+impl Default for TextAlignment {
+    fn default() -> Self {
+        TextAlignment::Left
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RecordedCell {
+    pub(crate) coord: Coord,
+    pub(crate) depth: i8,
+    pub(crate) view_cell: ViewCell,
+}
+
+impl RecordedCell {
+    pub(crate) fn is_space(&self) -> bool {
+        match self.view_cell.character {
+            None | Some(' ') => true,
+            Some(_) => false,
+        }
+    }
+}
+
+/// Cells drawn by a wrap, held back until the whole text has been laid out
+/// so that each line can be moved according to the alignment.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AlignBuffer {
+    cells: Vec<RecordedCell>,
+    paragraph_ends: Vec<i32>,
+    row: Vec<RecordedCell>,
+}
+
+/// Records cells drawn relative to the view, and passes everything else
+/// straight through to the underlying frame.
+pub(crate) struct RecordFrame<'a, F> {
+    pub(crate) cells: &'a mut Vec<RecordedCell>,
+    pub(crate) frame: &'a mut F,
+}
+
+impl<'a, F: Frame> Frame for RecordFrame<'a, F> {
+    fn set_cell_relative<C: ColModify>(
+        &mut self,
+        relative_coord: Coord,
+        relative_depth: i8,
+        relative_cell: ViewCell,
+        _context: ViewContext<C>,
+    ) {
+        self.cells.push(RecordedCell {
+            coord: relative_coord,
+            depth: relative_depth,
+            view_cell: relative_cell,
+        });
+    }
+    fn set_cell_absolute(
+        &mut self,
+        absolute_coord: Coord,
+        absolute_depth: i8,
+        absolute_cell: ViewCell,
+    ) {
+        self.frame
+            .set_cell_absolute(absolute_coord, absolute_depth, absolute_cell);
+    }
+    fn blend_cell_background_absolute<B: Blend>(
+        &mut self,
+        absolute_coord: Coord,
+        absolute_depth: i8,
+        rgb24: Rgb24,
+        alpha: u8,
+        blend: B,
+    ) {
+        self.frame.blend_cell_background_absolute(
+            absolute_coord,
+            absolute_depth,
+            rgb24,
+            alpha,
+            blend,
+        );
+    }
+}
+
+/// Drives a wrap on behalf of a text view, applying an alignment to its output
+pub(crate) struct AlignedWrap<'a, W> {
+    pub(crate) wrap: &'a mut W,
+    pub(crate) alignment: TextAlignment,
+    pub(crate) buffer: &'a mut AlignBuffer,
+}
+
+impl<'a, W: Wrap> AlignedWrap<'a, W> {
+    pub(crate) fn next_column(&self, width: u32) -> Option<u32> {
+        self.wrap.next_column(width)
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.wrap.clear();
+        self.buffer.cells.clear();
+        self.buffer.paragraph_ends.clear();
+    }
+
+    pub(crate) fn process_character<F: Frame, C: ColModify>(
+        &mut self,
+        character: char,
+        style: Style,
+        context: ViewContext<C>,
+        frame: &mut F,
+    ) {
+        if self.alignment == TextAlignment::Left {
+            self.wrap
+                .process_character(character, style, context, frame);
+            return;
+        }
+        let mut record_frame = RecordFrame {
+            cells: &mut self.buffer.cells,
+            frame,
+        };
+        self.wrap
+            .process_character(character, style, context, &mut record_frame);
+        if character == '\n' {
+            // the wrap has moved on to the line after the one which just ended
+            let paragraph_end = self.wrap.num_lines() as i32 - 2;
+            self.buffer.paragraph_ends.push(paragraph_end);
+        }
+    }
+
+    pub(crate) fn flush<F: Frame, C: ColModify>(&mut self, context: ViewContext<C>, frame: &mut F) {
+        if self.alignment == TextAlignment::Left {
+            self.wrap.flush(context, frame);
+            return;
+        }
+        let mut record_frame = RecordFrame {
+            cells: &mut self.buffer.cells,
+            frame,
+        };
+        self.wrap.flush(context, &mut record_frame);
+        self.buffer
+            .paragraph_ends
+            .push(self.wrap.num_lines() as i32 - 1);
+        let AlignBuffer {
+            cells,
+            paragraph_ends,
+            row,
+        } = &mut *self.buffer;
+        cells.sort_by_key(|cell| (cell.coord.y, cell.coord.x));
+        let width = context.size.width() as i32;
+        let mut cells = cells.drain(..).peekable();
+        while let Some(first) = cells.next() {
+            let y = first.coord.y;
+            row.clear();
+            row.push(first);
+            while let Some(cell) = cells.peek() {
+                if cell.coord.y != y {
+                    break;
+                }
+                row.push(*cell);
+                cells.next();
+            }
+            // trailing spaces would otherwise count towards the line's width
+            while row.last().map(RecordedCell::is_space).unwrap_or(false) {
+                row.pop();
+            }
+            let row_width = match row.last() {
+                Some(last) => last.coord.x + 1,
+                None => continue,
+            };
+            let spare = (width - row_width).max(0);
+            match self.alignment {
+                TextAlignment::Left => draw_row_shifted(row, 0, context, frame),
+                TextAlignment::Centre => draw_row_shifted(row, spare / 2, context, frame),
+                TextAlignment::Right => draw_row_shifted(row, spare, context, frame),
+                TextAlignment::Justify => {
+                    if paragraph_ends.contains(&y) {
+                        draw_row_shifted(row, 0, context, frame);
+                    } else {
+                        draw_row_justified(row, spare, context, frame);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn draw_row_shifted<F: Frame, C: ColModify>(
+    row: &[RecordedCell],
+    shift: i32,
+    context: ViewContext<C>,
+    frame: &mut F,
+) {
+    for cell in row {
+        frame.set_cell_relative(
+            cell.coord + Coord::new(shift, 0),
+            cell.depth,
+            cell.view_cell,
+            context,
+        );
+    }
+}
+
+fn draw_row_justified<F: Frame, C: ColModify>(
+    row: &[RecordedCell],
+    spare: i32,
+    context: ViewContext<C>,
+    frame: &mut F,
+) {
+    // a gap is a run of spaces between two words, not counting indentation
+    let first_word_index = row.iter().position(|cell| !cell.is_space()).unwrap_or(0);
+    let num_gaps = row
+        .windows(2)
+        .skip(first_word_index)
+        .filter(|pair| pair[0].is_space() && !pair[1].is_space())
+        .count();
+    let mut gap_widths = justified_gap_widths(spare as usize, num_gaps);
+    let mut shift = 0;
+    for (i, cell) in row.iter().enumerate() {
+        let ends_gap = i > first_word_index
+            && !cell.is_space()
+            && row.get(i - 1).map(RecordedCell::is_space).unwrap_or(false);
+        if ends_gap {
+            let extra = gap_widths.next().unwrap_or(0);
+            let space = row[i - 1];
+            for _ in 0..extra {
+                frame.set_cell_relative(
+                    space.coord + Coord::new(shift + 1, 0),
+                    space.depth,
+                    space.view_cell,
+                    context,
+                );
+                shift += 1;
+            }
+        }
+        frame.set_cell_relative(
+            cell.coord + Coord::new(shift, 0),
+            cell.depth,
+            cell.view_cell,
+            context,
+        );
+    }
+}
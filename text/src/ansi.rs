@@ -0,0 +1,411 @@
+use crate::rich_text::RichTextPartOwned;
+use chargrid_render::*;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+const ESCAPE: char = '\u{1b}';
+const BEL: char = '\u{7}';
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnsiErrorKind {
+    /// An escape sequence other than a control sequence (e.g. OSC hyperlinks)
+    UnsupportedEscape(char),
+    /// A control sequence other than SGR, identified by its final byte
+    UnsupportedControlSequence(char),
+    UnsupportedSgrParameter(u32),
+    /// An SGR parameter which isn't a number, or is too large
+    InvalidSgrParameter,
+    /// An extended colour (38 or 48) with missing or out-of-range components
+    InvalidExtendedColour,
+    /// The input ended part way through an escape sequence
+    UnterminatedEscape,
+}
+
+/// An error encountered while parsing ANSI-escaped text. The offset is in bytes
+/// from the start of the input, and refers to the start of the escape sequence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnsiError {
+    pub offset: usize,
+    pub kind: AnsiErrorKind,
+}
+
+/// Colours of the 16 standard ANSI colour codes, with 0-7 being the normal
+/// colours and 8-15 their bright variants
+pub const ANSI_COLOURS: [Rgb24; 16] = [
+    Rgb24::new(0, 0, 0),
+    Rgb24::new(170, 0, 0),
+    Rgb24::new(0, 170, 0),
+    Rgb24::new(170, 170, 0),
+    Rgb24::new(0, 0, 170),
+    Rgb24::new(170, 0, 170),
+    Rgb24::new(0, 170, 170),
+    Rgb24::new(170, 170, 170),
+    Rgb24::new(85, 85, 85),
+    Rgb24::new(255, 85, 85),
+    Rgb24::new(85, 255, 85),
+    Rgb24::new(255, 255, 85),
+    Rgb24::new(85, 85, 255),
+    Rgb24::new(255, 85, 255),
+    Rgb24::new(85, 255, 255),
+    Rgb24::new(255, 255, 255),
+];
+
+/// The colour of a code in the 256 colour palette
+pub fn ansi_256_colour(code: u8) -> Rgb24 {
+    const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    match code {
+        0..=15 => ANSI_COLOURS[code as usize],
+        16..=231 => {
+            let index = code - 16;
+            Rgb24::new(
+                CUBE_LEVELS[(index / 36) as usize],
+                CUBE_LEVELS[((index / 6) % 6) as usize],
+                CUBE_LEVELS[(index % 6) as usize],
+            )
+        }
+        232..=255 => Rgb24::new_grey(8 + (code - 232) * 10),
+    }
+}
+
+fn extended_colour<I: Iterator<Item = Option<u32>>>(
+    params: &mut I,
+) -> Result<Rgb24, AnsiErrorKind> {
+    let mut component = || {
+        params
+            .next()
+            .flatten()
+            .filter(|&value| value <= 255)
+            .map(|value| value as u8)
+            .ok_or(AnsiErrorKind::InvalidExtendedColour)
+    };
+    match component()? {
+        5 => Ok(ansi_256_colour(component()?)),
+        2 => Ok(Rgb24::new(component()?, component()?, component()?)),
+        _ => Err(AnsiErrorKind::InvalidExtendedColour),
+    }
+}
+
+fn parse_sgr_param(param: &str) -> Option<u32> {
+    if param.is_empty() {
+        Some(0)
+    } else {
+        param.parse::<u32>().ok()
+    }
+}
+
+/// Apply the parameters of an SGR sequence to a style. Unsupported and
+/// invalid parameters are skipped, and the first is reported once the whole
+/// sequence is applied. An empty parameter is treated as 0. Extended colours
+/// may be given either as parameters separated by semicolons (38;2;r;g;b) or
+/// as ISO 8613-6 sub-parameters separated by colons (38:2::r:g:b), in which
+/// case the colour space identifier may be omitted (38:2:r:g:b).
+fn apply_sgr(params: &str, base_style: Style, style: &mut Style) -> Result<(), AnsiErrorKind> {
+    let mut params = params.split(';');
+    let mut result = Ok(());
+    while let Some(param_with_sub_params) = params.next() {
+        let mut sub_params = param_with_sub_params.split(':').map(parse_sgr_param);
+        let param = match sub_params.next().flatten() {
+            Some(param) => param,
+            None => {
+                result = result.and(Err(AnsiErrorKind::InvalidSgrParameter));
+                continue;
+            }
+        };
+        match param {
+            0 => *style = base_style,
+            1 => style.bold = Some(true),
+            22 => style.bold = base_style.bold,
+            4 => style.underline = Some(true),
+            24 => style.underline = base_style.underline,
+            30..=37 => style.foreground = Some(ANSI_COLOURS[(param - 30) as usize]),
+            90..=97 => style.foreground = Some(ANSI_COLOURS[(param - 90 + 8) as usize]),
+            39 => style.foreground = base_style.foreground,
+            40..=47 => style.background = Some(ANSI_COLOURS[(param - 40) as usize]),
+            100..=107 => style.background = Some(ANSI_COLOURS[(param - 100 + 8) as usize]),
+            49 => style.background = base_style.background,
+            38 | 48 => {
+                let colour = if param_with_sub_params.contains(':') {
+                    let mut sub_params = sub_params.collect::<Vec<_>>();
+                    // skip the colour space identifier before the components
+                    if sub_params.len() == 5 && sub_params[0] == Some(2) {
+                        sub_params.remove(1);
+                    }
+                    extended_colour(&mut sub_params.into_iter())
+                } else {
+                    extended_colour(&mut params.by_ref().map(parse_sgr_param))
+                };
+                match colour {
+                    Ok(rgb24) if param == 38 => style.foreground = Some(rgb24),
+                    Ok(rgb24) => style.background = Some(rgb24),
+                    Err(kind) => result = result.and(Err(kind)),
+                }
+            }
+            other => result = result.and(Err(AnsiErrorKind::UnsupportedSgrParameter(other))),
+        }
+    }
+    result
+}
+
+/// Skip the body of a control string (e.g. OSC), returning whether it was
+/// terminated. A control string ends with BEL or the string terminator
+/// ESC \. Any other escape sequence also ends it, and is left to be parsed.
+fn skip_control_string(chars: &mut Peekable<CharIndices>) -> bool {
+    while let Some(&(_, character)) = chars.peek() {
+        if character == ESCAPE {
+            let mut lookahead = chars.clone();
+            lookahead.next();
+            if let Some((_, '\\')) = lookahead.next() {
+                *chars = lookahead;
+            }
+            return true;
+        }
+        chars.next();
+        if character == BEL {
+            return true;
+        }
+    }
+    false
+}
+
+/// Skip the intermediate bytes and final byte of an escape sequence whose
+/// first intermediate byte has already been read (e.g. the "B" of ESC ( B),
+/// returning whether it was terminated
+fn skip_escape_intermediates(chars: &mut Peekable<CharIndices>) -> bool {
+    for (_, character) in chars {
+        if !(' '..='/').contains(&character) {
+            return true;
+        }
+    }
+    false
+}
+
+fn parse_ansi_with_policy(
+    input: &str,
+    base_style: Style,
+    strict: bool,
+) -> Result<Vec<RichTextPartOwned>, AnsiError> {
+    let mut parts: Vec<RichTextPartOwned> = Vec::new();
+    let mut style = base_style;
+    let mut text = String::new();
+    let mut chars = input.char_indices().peekable();
+    let report = |offset, kind| {
+        if strict {
+            Err(AnsiError { offset, kind })
+        } else {
+            Ok(())
+        }
+    };
+    while let Some((offset, character)) = chars.next() {
+        if character != ESCAPE {
+            text.push(character);
+            continue;
+        }
+        match chars.next() {
+            Some((_, '[')) => (),
+            Some((_, introducer @ (']' | 'P' | 'X' | '^' | '_'))) => {
+                if skip_control_string(&mut chars) {
+                    report(offset, AnsiErrorKind::UnsupportedEscape(introducer))?;
+                } else {
+                    report(offset, AnsiErrorKind::UnterminatedEscape)?;
+                }
+                continue;
+            }
+            Some((_, intermediate @ ' '..='/')) => {
+                if skip_escape_intermediates(&mut chars) {
+                    report(offset, AnsiErrorKind::UnsupportedEscape(intermediate))?;
+                } else {
+                    report(offset, AnsiErrorKind::UnterminatedEscape)?;
+                }
+                continue;
+            }
+            Some((_, other)) => {
+                report(offset, AnsiErrorKind::UnsupportedEscape(other))?;
+                continue;
+            }
+            None => {
+                report(offset, AnsiErrorKind::UnterminatedEscape)?;
+                continue;
+            }
+        }
+        // a control sequence is parameter and intermediate bytes followed by a
+        // single final byte in the range '@' to '~'
+        let params_start = offset + 2;
+        let mut final_byte = None;
+        for (i, c) in chars.by_ref() {
+            if ('@'..='~').contains(&c) {
+                final_byte = Some((i, c));
+                break;
+            }
+        }
+        let (params_end, final_byte) = match final_byte {
+            Some(final_byte) => final_byte,
+            None => {
+                report(offset, AnsiErrorKind::UnterminatedEscape)?;
+                continue;
+            }
+        };
+        if final_byte != 'm' {
+            report(
+                offset,
+                AnsiErrorKind::UnsupportedControlSequence(final_byte),
+            )?;
+            continue;
+        }
+        let previous_style = style;
+        let result = apply_sgr(&input[params_start..params_end], base_style, &mut style);
+        if let Err(kind) = result {
+            report(offset, kind)?;
+        }
+        if style != previous_style && !text.is_empty() {
+            parts.push(RichTextPartOwned::new(text.clone(), previous_style));
+            text.clear();
+        }
+    }
+    if !text.is_empty() {
+        parts.push(RichTextPartOwned::new(text, style));
+    }
+    Ok(parts)
+}
+
+/// Convert text containing ANSI escape sequences into rich text parts.
+/// SGR sequences for bold, underline, 16 colour, 256 colour and true colour
+/// foregrounds and backgrounds, and reset are supported, with `base_style`
+/// being the style before any SGR sequence and after a reset. Unsupported
+/// escape sequences, such as OSC hyperlinks and character set designations,
+/// are removed from the output, and invalid SGR parameters are ignored.
+pub fn parse_ansi(input: &str, base_style: Style) -> Vec<RichTextPartOwned> {
+    match parse_ansi_with_policy(input, base_style, false) {
+        Ok(parts) => parts,
+        Err(_) => unreachable!("errors are only reported in strict mode"),
+    }
+}
+
+/// Like `parse_ansi`, but fails on the first unsupported escape sequence
+pub fn parse_ansi_strict(
+    input: &str,
+    base_style: Style,
+) -> Result<Vec<RichTextPartOwned>, AnsiError> {
+    parse_ansi_with_policy(input, base_style, true)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sgr() {
+        let parts = parse_ansi(
+            "a\u{1b}[1;31mb\u{1b}[38;5;196mc\u{1b}[48;2;1;2;3md\u{1b}[0me\u{1b}[2Kf",
+            Style::new(),
+        );
+        let parts = parts
+            .iter()
+            .map(|part| (part.text.as_str(), part.style))
+            .collect::<Vec<_>>();
+        let bold = Style::new().with_bold(true);
+        assert_eq!(
+            parts,
+            &[
+                ("a", Style::new()),
+                ("b", bold.with_foreground(ANSI_COLOURS[1])),
+                ("c", bold.with_foreground(Rgb24::new(255, 0, 0))),
+                (
+                    "d",
+                    bold.with_foreground(Rgb24::new(255, 0, 0))
+                        .with_background(Rgb24::new(1, 2, 3))
+                ),
+                ("ef", Style::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn colon_separated_extended_colours() {
+        let expected = Style::new()
+            .with_foreground(Rgb24::new(1, 2, 3))
+            .with_background(Rgb24::new(4, 5, 6));
+        for input in &[
+            "\u{1b}[38:2::1:2:3;48:2::4:5:6mx",
+            "\u{1b}[38:2:1:2:3;48:2:4:5:6mx",
+            "\u{1b}[38:2:0:1:2:3;48;2;4;5;6mx",
+        ] {
+            let parts = parse_ansi(input, Style::new());
+            assert_eq!(parts[0].style, expected, "{:?}", input);
+        }
+        let parts = parse_ansi("\u{1b}[38:5:196;1mx", Style::new());
+        assert_eq!(
+            parts[0].style,
+            Style::new()
+                .with_foreground(Rgb24::new(255, 0, 0))
+                .with_bold(true)
+        );
+    }
+
+    fn text(parts: &[RichTextPartOwned]) -> String {
+        parts.iter().map(|part| part.text.as_str()).collect()
+    }
+
+    #[test]
+    fn osc_and_charset() {
+        let hyperlink = "a\u{1b}]8;;http://example.com\u{7}b\u{1b}]8;;\u{1b}\\c";
+        assert_eq!(text(&parse_ansi(hyperlink, Style::new())), "abc");
+        assert_eq!(
+            text(&parse_ansi("a\u{1b}(Bb\u{1b})0c\u{1b}=d", Style::new())),
+            "abcd"
+        );
+        let parts = parse_ansi("a\u{1b}]0;title\u{1b}[1mb", Style::new());
+        assert_eq!(text(&parts), "ab");
+        assert_eq!(parts[1].style, Style::new().with_bold(true));
+        assert_eq!(text(&parse_ansi("a\u{1b}]0;title", Style::new())), "a");
+    }
+
+    #[test]
+    fn invalid_sgr_parameters_are_skipped() {
+        let bold = Style::new().with_bold(true);
+        for input in &[
+            "\u{1b}[38;5;300;1mx",
+            "\u{1b}[38;9;1mx",
+            "\u{1b}[99999999999;1mx",
+            "\u{1b}[?;1mx",
+        ] {
+            let parts = parse_ansi(input, Style::new());
+            assert_eq!(parts[0].style, bold, "{:?}", input);
+        }
+        let parts = parse_ansi("\u{1b}[;1mx", Style::new());
+        assert_eq!(parts[0].style, bold);
+    }
+
+    #[test]
+    fn strict() {
+        assert_eq!(
+            parse_ansi_strict("ab\u{1b}[2K", Style::new()).unwrap_err(),
+            AnsiError {
+                offset: 2,
+                kind: AnsiErrorKind::UnsupportedControlSequence('K'),
+            }
+        );
+        assert_eq!(
+            parse_ansi_strict("\u{1b}[7m", Style::new())
+                .unwrap_err()
+                .kind,
+            AnsiErrorKind::UnsupportedSgrParameter(7),
+        );
+        assert_eq!(
+            parse_ansi_strict("\u{1b}[99999999999m", Style::new())
+                .unwrap_err()
+                .kind,
+            AnsiErrorKind::InvalidSgrParameter,
+        );
+        assert_eq!(
+            parse_ansi_strict("a\u{1b}]8;;x\u{7}", Style::new()).unwrap_err(),
+            AnsiError {
+                offset: 1,
+                kind: AnsiErrorKind::UnsupportedEscape(']'),
+            }
+        );
+        assert_eq!(
+            parse_ansi_strict("\u{1b}(", Style::new()).unwrap_err().kind,
+            AnsiErrorKind::UnterminatedEscape,
+        );
+    }
+}
@@ -0,0 +1,247 @@
+use crate::align::AlignedWrap;
+use crate::wrap::Wrap;
+use chargrid_render::*;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+const VISIBLE_SPACE: char = '·';
+const VISIBLE_TAB: char = '→';
+const REPLACEMENT_CHARACTER: char = '\u{fffd}';
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TabExpansion {
+    /// Tabs are treated like any other control character
+    Disabled,
+    /// Tab stops every this many columns. A width of 0 is treated as 1.
+    Width(u32),
+    /// Tab stops at these columns, which must be in ascending order (this is
+    /// checked in debug builds). Tabs beyond the last stop are expanded to a
+    /// single space.
+    Stops(Vec<u32>),
+}
+
+impl Default for TabExpansion {
+    fn default() -> Self {
+        TabExpansion::Disabled
+    }
+}
+
+/// How to display control characters other than newline and carriage return
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlCharacters {
+    /// Pass the character through unchanged
+    Raw,
+    /// Caret notation, such as `^[` for escape. Control characters outside
+    /// of ASCII are shown as the replacement character.
+    Caret,
+    /// Replace each control character with this character
+    Replace(char),
+    /// Drop control characters entirely
+    Skip,
+}
+
+impl Default for ControlCharacters {
+    fn default() -> Self {
+        ControlCharacters::Raw
+    }
+}
+
+/// Controls how tabs, other control characters and whitespace are displayed.
+/// Columns used for tab stops are counted from the start of the row the tab
+/// is drawn on, or from the last newline for wraps which can't report their
+/// column.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CharacterExpansion {
+    pub tabs: TabExpansion,
+    pub control_characters: ControlCharacters,
+    /// Draw spaces as '·' and the start of each tab as '→'
+    pub visible_whitespace: bool,
+}
+
+impl CharacterExpansion {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_tabs(self, tabs: TabExpansion) -> Self {
+        Self { tabs, ..self }
+    }
+    pub fn with_control_characters(self, control_characters: ControlCharacters) -> Self {
+        Self {
+            control_characters,
+            ..self
+        }
+    }
+    pub fn with_visible_whitespace(self, visible_whitespace: bool) -> Self {
+        Self {
+            visible_whitespace,
+            ..self
+        }
+    }
+    fn tab_width(&self, column: u32) -> Option<u32> {
+        match &self.tabs {
+            TabExpansion::Disabled => None,
+            TabExpansion::Width(width) => {
+                let width = (*width).max(1);
+                Some(width - (column % width))
+            }
+            TabExpansion::Stops(stops) => {
+                debug_assert!(
+                    stops.windows(2).all(|pair| pair[0] < pair[1]),
+                    "tab stops must be in ascending order"
+                );
+                Some(
+                    stops
+                        .iter()
+                        .find(|&&stop| stop > column)
+                        .map(|stop| stop - column)
+                        .unwrap_or(1),
+                )
+            }
+        }
+    }
+}
+
+/// Draws spaces as visible characters
+struct VisibleWhitespaceFrame<'a, F> {
+    frame: &'a mut F,
+}
+
+impl<'a, F: Frame> Frame for VisibleWhitespaceFrame<'a, F> {
+    fn set_cell_relative<C: ColModify>(
+        &mut self,
+        relative_coord: Coord,
+        relative_depth: i8,
+        mut relative_cell: ViewCell,
+        context: ViewContext<C>,
+    ) {
+        if relative_cell.character == Some(' ') {
+            relative_cell.character = Some(VISIBLE_SPACE);
+        }
+        self.frame
+            .set_cell_relative(relative_coord, relative_depth, relative_cell, context);
+    }
+    fn set_cell_absolute(
+        &mut self,
+        absolute_coord: Coord,
+        absolute_depth: i8,
+        absolute_cell: ViewCell,
+    ) {
+        self.frame
+            .set_cell_absolute(absolute_coord, absolute_depth, absolute_cell);
+    }
+    fn blend_cell_background_absolute<B: Blend>(
+        &mut self,
+        absolute_coord: Coord,
+        absolute_depth: i8,
+        rgb24: Rgb24,
+        alpha: u8,
+        blend: B,
+    ) {
+        self.frame.blend_cell_background_absolute(
+            absolute_coord,
+            absolute_depth,
+            rgb24,
+            alpha,
+            blend,
+        );
+    }
+}
+
+/// Drives a wrap on behalf of a text view, expanding characters according to
+/// a `CharacterExpansion` before they reach the wrap
+pub(crate) struct ExpandedWrap<'a, W> {
+    pub(crate) wrap: AlignedWrap<'a, W>,
+    pub(crate) expansion: &'a CharacterExpansion,
+    pub(crate) column: u32,
+}
+
+impl<'a, W: Wrap> ExpandedWrap<'a, W> {
+    pub(crate) fn clear(&mut self) {
+        self.wrap.clear();
+        self.column = 0;
+    }
+
+    fn emit<F: Frame, C: ColModify>(
+        &mut self,
+        character: char,
+        style: Style,
+        context: ViewContext<C>,
+        frame: &mut F,
+    ) {
+        if self.expansion.visible_whitespace {
+            let mut frame = VisibleWhitespaceFrame { frame };
+            self.wrap
+                .process_character(character, style, context, &mut frame);
+        } else {
+            self.wrap
+                .process_character(character, style, context, frame);
+        }
+        self.column += 1;
+    }
+
+    pub(crate) fn process_character<F: Frame, C: ColModify>(
+        &mut self,
+        character: char,
+        style: Style,
+        context: ViewContext<C>,
+        frame: &mut F,
+    ) {
+        match character {
+            '\n' | '\r' => {
+                self.emit(character, style, context, frame);
+                self.column = 0;
+                return;
+            }
+            '\t' => {
+                let column = self
+                    .wrap
+                    .next_column(context.size.width())
+                    .unwrap_or(self.column);
+                if let Some(width) = self.expansion.tab_width(column) {
+                    for i in 0..width {
+                        let character = if i == 0 && self.expansion.visible_whitespace {
+                            VISIBLE_TAB
+                        } else {
+                            ' '
+                        };
+                        self.emit(character, style, context, frame);
+                    }
+                    return;
+                }
+            }
+            _ => (),
+        }
+        if !character.is_control() {
+            self.emit(character, style, context, frame);
+            return;
+        }
+        match self.expansion.control_characters {
+            ControlCharacters::Raw => self.emit(character, style, context, frame),
+            ControlCharacters::Caret => {
+                if (character as u32) < 0x80 {
+                    let caret = ((character as u8) ^ 0x40) as char;
+                    self.emit('^', style, context, frame);
+                    self.emit(caret, style, context, frame);
+                } else {
+                    self.emit(REPLACEMENT_CHARACTER, style, context, frame);
+                }
+            }
+            ControlCharacters::Replace(replacement) => {
+                self.emit(replacement, style, context, frame)
+            }
+            ControlCharacters::Skip => (),
+        }
+    }
+
+    pub(crate) fn flush<F: Frame, C: ColModify>(&mut self, context: ViewContext<C>, frame: &mut F) {
+        if self.expansion.visible_whitespace {
+            self.wrap
+                .flush(context, &mut VisibleWhitespaceFrame { frame });
+        } else {
+            self.wrap.flush(context, frame);
+        }
+    }
+}
@@ -0,0 +1,472 @@
+use crate::rich_text::RichTextPart;
+use chargrid_render::*;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    Plain,
+    Keyword,
+    /// Keys of objects and tables
+    Key,
+    String,
+    Number,
+    /// Constants such as `true` and `null`
+    Literal,
+    Comment,
+    Punctuation,
+}
+
+/// A region of source text, as a range of byte offsets
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub range: Range<usize>,
+}
+
+/// Splits source text into tokens. Tokens must be in order and must not
+/// overlap. Any text not covered by a token is considered plain.
+pub trait Highlighter {
+    fn tokenize(&self, source: &str, tokens: &mut Vec<Token>);
+}
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy)]
+pub struct HighlightTheme {
+    pub plain: Style,
+    pub keyword: Style,
+    pub key: Style,
+    pub string: Style,
+    pub number: Style,
+    pub literal: Style,
+    pub comment: Style,
+    pub punctuation: Style,
+}
+
+impl HighlightTheme {
+    pub fn new() -> Self {
+        Self {
+            plain: Style::new().with_foreground(Rgb24::new_grey(255)),
+            keyword: Style::new()
+                .with_foreground(Rgb24::new(198, 120, 221))
+                .with_bold(true),
+            key: Style::new().with_foreground(Rgb24::new(97, 175, 239)),
+            string: Style::new().with_foreground(Rgb24::new(152, 195, 121)),
+            number: Style::new().with_foreground(Rgb24::new(209, 154, 102)),
+            literal: Style::new().with_foreground(Rgb24::new(86, 182, 194)),
+            comment: Style::new().with_foreground(Rgb24::new_grey(127)),
+            punctuation: Style::new().with_foreground(Rgb24::new_grey(187)),
+        }
+    }
+    pub fn style(&self, kind: TokenKind) -> Style {
+        match kind {
+            TokenKind::Plain => self.plain,
+            TokenKind::Keyword => self.keyword,
+            TokenKind::Key => self.key,
+            TokenKind::String => self.string,
+            TokenKind::Number => self.number,
+            TokenKind::Literal => self.literal,
+            TokenKind::Comment => self.comment,
+            TokenKind::Punctuation => self.punctuation,
+        }
+    }
+}
+
+impl Default for HighlightTheme {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Split source text into rich text parts styled according to the theme,
+/// for rendering with a `RichTextView`
+pub fn highlight<'a, H: Highlighter + ?Sized>(
+    source: &'a str,
+    highlighter: &H,
+    theme: &HighlightTheme,
+) -> Vec<RichTextPart<'a>> {
+    let mut tokens = Vec::new();
+    highlighter.tokenize(source, &mut tokens);
+    let mut runs: Vec<(Range<usize>, Style)> = Vec::new();
+    let mut push = |range: Range<usize>, style: Style| {
+        if range.is_empty() {
+            return;
+        }
+        match runs.last_mut() {
+            Some((last, last_style)) if *last_style == style => last.end = range.end,
+            _ => runs.push((range, style)),
+        }
+    };
+    let mut position = 0;
+    for token in tokens {
+        push(position..token.range.start, theme.plain);
+        push(token.range.clone(), theme.style(token.kind));
+        position = token.range.end;
+    }
+    push(position..source.len(), theme.plain);
+    runs.into_iter()
+        .map(|(range, style)| RichTextPart::new(&source[range], style))
+        .collect()
+}
+
+/// A cursor over source text for building tokenizers
+struct Scanner<'a> {
+    source: &'a str,
+    position: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            position: 0,
+        }
+    }
+    fn rest(&self) -> &'a str {
+        &self.source[self.position..]
+    }
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+    fn bump(&mut self) -> Option<char> {
+        let character = self.peek()?;
+        self.position += character.len_utf8();
+        Some(character)
+    }
+    fn eat_while<F: FnMut(char) -> bool>(&mut self, mut f: F) {
+        while let Some(character) = self.peek() {
+            if !f(character) {
+                break;
+            }
+            self.bump();
+        }
+    }
+    fn eat_str(&mut self, prefix: &str) -> bool {
+        if self.rest().starts_with(prefix) {
+            self.position += prefix.len();
+            true
+        } else {
+            false
+        }
+    }
+    /// Consume up to and including `end`, or to the end of the source
+    fn eat_until(&mut self, end: &str) {
+        match self.rest().find(end) {
+            Some(index) => self.position += index + end.len(),
+            None => self.position = self.source.len(),
+        }
+    }
+    /// Consume the remainder of a quoted string, whose opening quote has
+    /// already been consumed
+    fn eat_string(&mut self, quote: char, escapes: bool) {
+        while let Some(character) = self.bump() {
+            if character == quote {
+                break;
+            }
+            if escapes && character == '\\' {
+                self.bump();
+            }
+        }
+    }
+    fn eat_number(&mut self) {
+        self.eat_while(|c| c.is_alphanumeric() || c == '_' || c == '.');
+    }
+    fn next_non_whitespace(&self) -> Option<char> {
+        self.rest().chars().find(|c| !c.is_whitespace())
+    }
+}
+
+fn is_identifier_start(character: char) -> bool {
+    character.is_alphabetic() || character == '_'
+}
+
+fn is_identifier_continue(character: char) -> bool {
+    character.is_alphanumeric() || character == '_'
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonHighlighter;
+
+impl Highlighter for JsonHighlighter {
+    fn tokenize(&self, source: &str, tokens: &mut Vec<Token>) {
+        let mut scanner = Scanner::new(source);
+        while let Some(character) = scanner.peek() {
+            let start = scanner.position;
+            let kind = match character {
+                '"' => {
+                    scanner.bump();
+                    scanner.eat_string('"', true);
+                    if scanner.next_non_whitespace() == Some(':') {
+                        TokenKind::Key
+                    } else {
+                        TokenKind::String
+                    }
+                }
+                '-' | '0'..='9' => {
+                    scanner.bump();
+                    scanner.eat_while(|c| c.is_ascii_digit() || "+-.eE".contains(c));
+                    TokenKind::Number
+                }
+                c if c.is_alphabetic() => {
+                    scanner.eat_while(char::is_alphabetic);
+                    TokenKind::Literal
+                }
+                c if c.is_whitespace() => {
+                    scanner.bump();
+                    continue;
+                }
+                _ => {
+                    scanner.bump();
+                    TokenKind::Punctuation
+                }
+            };
+            tokens.push(Token {
+                kind,
+                range: start..scanner.position,
+            });
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TomlHighlighter;
+
+impl Highlighter for TomlHighlighter {
+    fn tokenize(&self, source: &str, tokens: &mut Vec<Token>) {
+        let mut scanner = Scanner::new(source);
+        // brackets of the arrays and inline tables enclosing the current token
+        let mut brackets = Vec::new();
+        // keys appear at the start of lines and after '{' and ',' in inline tables
+        let mut expect_key = true;
+        while let Some(character) = scanner.peek() {
+            let start = scanner.position;
+            let kind = match character {
+                '\n' => {
+                    scanner.bump();
+                    expect_key = brackets.is_empty();
+                    continue;
+                }
+                c if c.is_whitespace() => {
+                    scanner.bump();
+                    continue;
+                }
+                '#' => {
+                    scanner.eat_while(|c| c != '\n');
+                    TokenKind::Comment
+                }
+                '[' if expect_key && brackets.is_empty() => {
+                    // table header
+                    scanner.eat_while(|c| c != ']' && c != '\n');
+                    scanner.eat_while(|c| c == ']');
+                    TokenKind::Key
+                }
+                '"' | '\'' => {
+                    let triple = if character == '"' { "\"\"\"" } else { "'''" };
+                    if scanner.eat_str(triple) {
+                        scanner.eat_until(triple);
+                    } else {
+                        scanner.bump();
+                        scanner.eat_string(character, character == '"');
+                    }
+                    if expect_key {
+                        TokenKind::Key
+                    } else {
+                        TokenKind::String
+                    }
+                }
+                c if expect_key && (is_identifier_continue(c) || c == '-') => {
+                    scanner.eat_while(|c| is_identifier_continue(c) || c == '-');
+                    TokenKind::Key
+                }
+                '+' | '-' | '0'..='9' => {
+                    scanner.bump();
+                    scanner.eat_while(|c| c.is_alphanumeric() || "_.:+-".contains(c));
+                    TokenKind::Number
+                }
+                c if c.is_alphabetic() => {
+                    scanner.eat_while(char::is_alphanumeric);
+                    TokenKind::Literal
+                }
+                _ => {
+                    scanner.bump();
+                    match character {
+                        '=' => expect_key = false,
+                        '[' => brackets.push(character),
+                        '{' => {
+                            brackets.push(character);
+                            expect_key = true;
+                        }
+                        ']' | '}' => {
+                            brackets.pop();
+                        }
+                        ',' => expect_key = brackets.last() == Some(&'{'),
+                        _ => (),
+                    }
+                    TokenKind::Punctuation
+                }
+            };
+            tokens.push(Token {
+                kind,
+                range: start..scanner.position,
+            });
+        }
+    }
+}
+
+/// A highlighter for languages with C-like lexical structure, configured with
+/// the language's keywords, literals, comment syntax and string delimiters
+#[derive(Debug, Clone)]
+pub struct CodeHighlighter {
+    pub keywords: Vec<&'static str>,
+    pub literals: Vec<&'static str>,
+    pub line_comment: &'static str,
+    pub block_comment: Option<(&'static str, &'static str)>,
+    pub string_delimiters: Vec<char>,
+}
+
+impl CodeHighlighter {
+    pub fn rust() -> Self {
+        Self {
+            keywords: vec![
+                "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else",
+                "enum", "extern", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod",
+                "move", "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super",
+                "trait", "type", "unsafe", "use", "where", "while",
+            ],
+            literals: vec!["true", "false", "None", "Some", "Ok", "Err"],
+            line_comment: "//",
+            block_comment: Some(("/*", "*/")),
+            string_delimiters: vec!['"'],
+        }
+    }
+    pub fn lua() -> Self {
+        Self {
+            keywords: vec![
+                "and", "break", "do", "else", "elseif", "end", "for", "function", "goto", "if",
+                "in", "local", "not", "or", "repeat", "return", "then", "until", "while",
+            ],
+            literals: vec!["true", "false", "nil"],
+            line_comment: "--",
+            block_comment: Some(("--[[", "]]")),
+            string_delimiters: vec!['"', '\''],
+        }
+    }
+}
+
+impl Highlighter for CodeHighlighter {
+    fn tokenize(&self, source: &str, tokens: &mut Vec<Token>) {
+        let mut scanner = Scanner::new(source);
+        while let Some(character) = scanner.peek() {
+            let start = scanner.position;
+            let kind = if character.is_whitespace() {
+                scanner.bump();
+                continue;
+            } else if let Some((open, close)) = self
+                .block_comment
+                .filter(|(open, _)| scanner.rest().starts_with(open))
+            {
+                scanner.eat_str(open);
+                scanner.eat_until(close);
+                TokenKind::Comment
+            } else if scanner.eat_str(self.line_comment) {
+                scanner.eat_while(|c| c != '\n');
+                TokenKind::Comment
+            } else if self.string_delimiters.contains(&character) {
+                scanner.bump();
+                scanner.eat_string(character, true);
+                TokenKind::String
+            } else if character.is_ascii_digit() {
+                scanner.eat_number();
+                TokenKind::Number
+            } else if is_identifier_start(character) {
+                scanner.eat_while(is_identifier_continue);
+                let word = &source[start..scanner.position];
+                if self.keywords.contains(&word) {
+                    TokenKind::Keyword
+                } else if self.literals.contains(&word) {
+                    TokenKind::Literal
+                } else {
+                    continue;
+                }
+            } else {
+                scanner.bump();
+                TokenKind::Punctuation
+            };
+            tokens.push(Token {
+                kind,
+                range: start..scanner.position,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn kinds<H: Highlighter>(highlighter: H, source: &str) -> Vec<(&str, TokenKind)> {
+        let mut tokens = Vec::new();
+        highlighter.tokenize(source, &mut tokens);
+        tokens
+            .into_iter()
+            .map(|token| (&source[token.range], token.kind))
+            .collect()
+    }
+
+    #[test]
+    fn json() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds(JsonHighlighter, r#"{"a": [1.5, "b", null]}"#),
+            &[
+                ("{", Punctuation),
+                ("\"a\"", Key),
+                (":", Punctuation),
+                ("[", Punctuation),
+                ("1.5", Number),
+                (",", Punctuation),
+                ("\"b\"", String),
+                (",", Punctuation),
+                ("null", Literal),
+                ("]", Punctuation),
+                ("}", Punctuation),
+            ]
+        );
+    }
+
+    #[test]
+    fn toml() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds(
+                TomlHighlighter,
+                "[table]\nkey = \"value\" # note\nn = [3, 4]"
+            ),
+            &[
+                ("[table]", Key),
+                ("key", Key),
+                ("=", Punctuation),
+                ("\"value\"", String),
+                ("# note", Comment),
+                ("n", Key),
+                ("=", Punctuation),
+                ("[", Punctuation),
+                ("3", Number),
+                (",", Punctuation),
+                ("4", Number),
+                ("]", Punctuation),
+            ]
+        );
+    }
+
+    #[test]
+    fn parts_cover_source() {
+        let source = "local x = 1 -- one\nreturn x";
+        let parts = highlight(source, &CodeHighlighter::lua(), &HighlightTheme::new());
+        let text = parts.iter().map(|part| part.text).collect::<String>();
+        assert_eq!(text, source);
+        assert_eq!(parts[0].text, "local");
+    }
+}
@@ -1,6 +1,24 @@
+mod align;
+mod ansi;
 mod default;
+mod expand;
+mod highlight;
+mod markup;
+mod measure;
 mod rich_text;
 mod text;
+mod text_editor;
+mod text_input;
+mod truncate;
 pub mod wrap;
+pub use align::TextAlignment;
+pub use ansi::*;
+pub use expand::*;
+pub use highlight::*;
+pub use markup::*;
+pub use measure::*;
 pub use rich_text::*;
 pub use text::*;
+pub use text_editor::*;
+pub use text_input::*;
+pub use truncate::Truncation;
@@ -0,0 +1,261 @@
+use crate::rich_text::RichTextPartOwned;
+use chargrid_render::*;
+use std::collections::HashMap;
+
+/// Named colours which may be used in place of hex codes in markup, as in
+/// `[fg=warning]...[/fg]`
+#[derive(Debug, Clone, Default)]
+pub struct MarkupPalette {
+    colours: HashMap<String, Rgb24>,
+}
+
+impl MarkupPalette {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_colour<S: Into<String>>(mut self, name: S, rgb24: Rgb24) -> Self {
+        self.insert(name, rgb24);
+        self
+    }
+    pub fn insert<S: Into<String>>(&mut self, name: S, rgb24: Rgb24) {
+        self.colours.insert(name.into(), rgb24);
+    }
+    pub fn get(&self, name: &str) -> Option<Rgb24> {
+        self.colours.get(name).cloned()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarkupErrorKind {
+    /// A '[' with no matching ']'
+    UnterminatedTag,
+    UnknownTag(String),
+    /// A tag which requires a value (e.g. `fg`) was given without one, or a
+    /// tag which takes no value (e.g. `b`) was given one
+    UnexpectedValue(String),
+    MissingValue(String),
+    UnknownColour(String),
+    /// A closing tag with no corresponding opening tag
+    UnexpectedCloseTag(String),
+    MismatchedCloseTag {
+        expected: String,
+        found: String,
+    },
+    /// The end of the markup was reached while this tag was still open
+    UnclosedTag(String),
+    InvalidEscape(char),
+    TrailingBackslash,
+}
+
+/// An error encountered while parsing markup. The offset is in bytes from the
+/// start of the markup string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkupError {
+    pub offset: usize,
+    pub kind: MarkupErrorKind,
+}
+
+struct OpenTag {
+    name: String,
+    offset: usize,
+    style: Style,
+}
+
+fn parse_colour(value: &str, palette: &MarkupPalette) -> Option<Rgb24> {
+    if let Some(hex) = value.strip_prefix('#') {
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |i| u8::from_str_radix(&hex[i..(i + 2)], 16).ok();
+        Some(Rgb24::new(channel(0)?, channel(2)?, channel(4)?))
+    } else {
+        palette.get(value)
+    }
+}
+
+fn tag_style(
+    name: &str,
+    value: Option<&str>,
+    palette: &MarkupPalette,
+) -> Result<Style, MarkupErrorKind> {
+    match (name, value) {
+        ("b", None) => Ok(Style::new().with_bold(true)),
+        ("u", None) => Ok(Style::new().with_underline(true)),
+        ("b", Some(_)) | ("u", Some(_)) => Err(MarkupErrorKind::UnexpectedValue(name.to_string())),
+        ("fg", Some(value)) | ("bg", Some(value)) => {
+            let rgb24 = parse_colour(value, palette)
+                .ok_or_else(|| MarkupErrorKind::UnknownColour(value.to_string()))?;
+            Ok(if name == "fg" {
+                Style::new().with_foreground(rgb24)
+            } else {
+                Style::new().with_background(rgb24)
+            })
+        }
+        ("fg", None) | ("bg", None) => Err(MarkupErrorKind::MissingValue(name.to_string())),
+        _ => Err(MarkupErrorKind::UnknownTag(name.to_string())),
+    }
+}
+
+/// Parse markup into rich text parts, using the default style for unstyled text.
+/// See `parse_markup_with_style` for a description of the syntax.
+pub fn parse_markup(
+    markup: &str,
+    palette: &MarkupPalette,
+) -> Result<Vec<RichTextPartOwned>, MarkupError> {
+    parse_markup_with_style(markup, Style::new(), palette)
+}
+
+/// Parse markup into rich text parts.
+///
+/// Supported tags are `[b]` (bold), `[u]` (underline), `[fg=...]` and
+/// `[bg=...]` (foreground and background colour), where colours are either
+/// hex codes such as `#ff0000` or names from the palette. Each tag is closed by
+/// the corresponding `[/b]`, `[/u]`, `[/fg]` or `[/bg]`. Tags may be nested,
+/// with inner tags taking precedence over outer tags, and `base_style` applying
+/// to anything not overridden by a tag. The characters `[`, `]` and `\` can be
+/// escaped with a preceding `\`.
+pub fn parse_markup_with_style(
+    markup: &str,
+    base_style: Style,
+    palette: &MarkupPalette,
+) -> Result<Vec<RichTextPartOwned>, MarkupError> {
+    let mut parts: Vec<RichTextPartOwned> = Vec::new();
+    let mut open_tags: Vec<OpenTag> = Vec::new();
+    let mut text = String::new();
+    let mut chars = markup.char_indices();
+    let current_style =
+        |open_tags: &[OpenTag]| open_tags.last().map(|tag| tag.style).unwrap_or(base_style);
+    let flush = |text: &mut String, style: Style, parts: &mut Vec<RichTextPartOwned>| {
+        if text.is_empty() {
+            return;
+        }
+        match parts.last_mut() {
+            Some(last) if last.style == style => last.text.push_str(text),
+            _ => parts.push(RichTextPartOwned::new(text.clone(), style)),
+        }
+        text.clear();
+    };
+    while let Some((offset, character)) = chars.next() {
+        match character {
+            '\\' => match chars.next() {
+                Some((_, escaped @ '[')) | Some((_, escaped @ ']')) | Some((_, escaped @ '\\')) => {
+                    text.push(escaped)
+                }
+                Some((_, other)) => {
+                    return Err(MarkupError {
+                        offset,
+                        kind: MarkupErrorKind::InvalidEscape(other),
+                    })
+                }
+                None => {
+                    return Err(MarkupError {
+                        offset,
+                        kind: MarkupErrorKind::TrailingBackslash,
+                    })
+                }
+            },
+            '[' => {
+                let tag_start = offset + 1;
+                let tag_end = match markup[tag_start..].find(']') {
+                    Some(len) => tag_start + len,
+                    None => {
+                        return Err(MarkupError {
+                            offset,
+                            kind: MarkupErrorKind::UnterminatedTag,
+                        })
+                    }
+                };
+                let tag = &markup[tag_start..tag_end];
+                // skip over the body of the tag and its closing ']'
+                for (i, _) in chars.by_ref() {
+                    if i == tag_end {
+                        break;
+                    }
+                }
+                flush(&mut text, current_style(&open_tags), &mut parts);
+                let error = |kind| MarkupError { offset, kind };
+                if let Some(name) = tag.strip_prefix('/') {
+                    let open_tag = open_tags.pop().ok_or_else(|| {
+                        error(MarkupErrorKind::UnexpectedCloseTag(name.to_string()))
+                    })?;
+                    if open_tag.name != name {
+                        return Err(error(MarkupErrorKind::MismatchedCloseTag {
+                            expected: open_tag.name,
+                            found: name.to_string(),
+                        }));
+                    }
+                } else {
+                    let (name, value) = match tag.find('=') {
+                        Some(i) => (&tag[..i], Some(&tag[(i + 1)..])),
+                        None => (tag, None),
+                    };
+                    let style = tag_style(name, value, palette)
+                        .map_err(error)?
+                        .coalesce(current_style(&open_tags));
+                    open_tags.push(OpenTag {
+                        name: name.to_string(),
+                        offset,
+                        style,
+                    });
+                }
+            }
+            other => text.push(other),
+        }
+    }
+    if let Some(open_tag) = open_tags.pop() {
+        return Err(MarkupError {
+            offset: open_tag.offset,
+            kind: MarkupErrorKind::UnclosedTag(open_tag.name),
+        });
+    }
+    flush(&mut text, base_style, &mut parts);
+    Ok(parts)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn nested_tags() {
+        let palette = MarkupPalette::new().with_colour("warning", Rgb24::new(255, 255, 0));
+        let parts = parse_markup("a [b]b [fg=warning]c\\[[/fg][/b]", &palette).unwrap();
+        let parts = parts
+            .iter()
+            .map(|part| (part.text.as_str(), part.style))
+            .collect::<Vec<_>>();
+        let bold = Style::new().with_bold(true);
+        assert_eq!(
+            parts,
+            &[
+                ("a ", Style::new()),
+                ("b ", bold),
+                ("c[", bold.with_foreground(Rgb24::new(255, 255, 0))),
+            ]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let palette = MarkupPalette::new();
+        let error = |markup| parse_markup(markup, &palette).unwrap_err();
+        assert_eq!(
+            error("ab [fg=nope]"),
+            MarkupError {
+                offset: 3,
+                kind: MarkupErrorKind::UnknownColour("nope".to_string()),
+            }
+        );
+        assert_eq!(
+            error("[b][u]x[/b]"),
+            MarkupError {
+                offset: 7,
+                kind: MarkupErrorKind::MismatchedCloseTag {
+                    expected: "u".to_string(),
+                    found: "b".to_string(),
+                },
+            }
+        );
+        assert_eq!(error("x[u").offset, 1);
+    }
+}
@@ -0,0 +1,310 @@
+use crate::wrap::Wrap;
+use chargrid_render::*;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+/// The layout of some text wrapped to a given width
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextMeasurement {
+    pub num_lines: usize,
+    /// The width of the widest line, not counting trailing spaces
+    pub max_line_width: u32,
+    /// The byte offset into the text of the first character of each line.
+    /// Spaces which the wrap left out at the end of a line count towards the
+    /// start of the next line.
+    pub line_starts: Vec<usize>,
+}
+
+impl TextMeasurement {
+    pub fn size(&self) -> Size {
+        Size::new(self.max_line_width, self.num_lines as u32)
+    }
+}
+
+/// Gives a character a style which encodes its index in the text, so that the
+/// cells a wrap draws identify the characters they came from. Wraps draw each
+/// character in the style it was given, and cells they insert (such as
+/// hyphens) take the style of an earlier character.
+fn index_style(index: usize) -> Style {
+    let index = index as u64;
+    Style::new()
+        .with_foreground(Rgb24::new(
+            (index >> 40) as u8,
+            (index >> 32) as u8,
+            (index >> 24) as u8,
+        ))
+        .with_background(Rgb24::new(
+            (index >> 16) as u8,
+            (index >> 8) as u8,
+            index as u8,
+        ))
+}
+
+fn style_index(style: Style) -> Option<usize> {
+    let foreground = style.foreground?;
+    let background = style.background?;
+    let index = [
+        foreground.r,
+        foreground.g,
+        foreground.b,
+        background.r,
+        background.g,
+        background.b,
+    ]
+    .iter()
+    .fold(0u64, |index, &byte| (index << 8) | byte as u64);
+    Some(index as usize)
+}
+
+/// Notes where each character is placed as a wrap draws it, and draws it in
+/// the style of the text rather than the style encoding its index
+struct PlacementFrame<'a, F> {
+    placements: &'a mut Vec<Option<Coord>>,
+    max_line_width: u32,
+    style: Style,
+    frame: Option<&'a mut F>,
+}
+
+impl<'a, F: Frame> Frame for PlacementFrame<'a, F> {
+    fn set_cell_relative<C: ColModify>(
+        &mut self,
+        relative_coord: Coord,
+        relative_depth: i8,
+        relative_cell: ViewCell,
+        context: ViewContext<C>,
+    ) {
+        if let Some(placement) =
+            style_index(relative_cell.style).and_then(|index| self.placements.get_mut(index))
+        {
+            placement.get_or_insert(relative_coord);
+        }
+        if matches!(relative_cell.character, Some(c) if c != ' ') {
+            self.max_line_width = self.max_line_width.max(relative_coord.x as u32 + 1);
+        }
+        if let Some(frame) = self.frame.as_mut() {
+            let view_cell = ViewCell {
+                style: self.style,
+                ..relative_cell
+            };
+            frame.set_cell_relative(relative_coord, relative_depth, view_cell, context);
+        }
+    }
+    fn set_cell_absolute(
+        &mut self,
+        absolute_coord: Coord,
+        absolute_depth: i8,
+        absolute_cell: ViewCell,
+    ) {
+        if let Some(frame) = self.frame.as_mut() {
+            frame.set_cell_absolute(absolute_coord, absolute_depth, absolute_cell);
+        }
+    }
+    fn blend_cell_background_absolute<B: Blend>(
+        &mut self,
+        absolute_coord: Coord,
+        absolute_depth: i8,
+        rgb24: Rgb24,
+        alpha: u8,
+        blend: B,
+    ) {
+        if let Some(frame) = self.frame.as_mut() {
+            frame.blend_cell_background_absolute(
+                absolute_coord,
+                absolute_depth,
+                rgb24,
+                alpha,
+                blend,
+            );
+        }
+    }
+}
+
+/// Lay out characters with a wrap, drawing them in `style` if a frame is
+/// given. The position of each character, followed by the position just after
+/// the last character, is written to `positions`, and the width of the widest
+/// line (not counting trailing spaces) is returned. Characters the wrap didn't
+/// draw, such as newlines and spaces dropped at the start of wrapped lines,
+/// are placed just after the character before them.
+pub(crate) fn wrap_characters<W, I, F, C>(
+    wrap: &mut W,
+    characters: I,
+    style: Style,
+    context: ViewContext<C>,
+    frame: Option<&mut F>,
+    positions: &mut Vec<Coord>,
+) -> u32
+where
+    W: Wrap,
+    I: IntoIterator<Item = char>,
+    F: Frame,
+    C: ColModify,
+{
+    let mut placements = Vec::new();
+    let mut is_newline = Vec::new();
+    let mut frame = PlacementFrame {
+        placements: &mut placements,
+        max_line_width: 0,
+        style,
+        frame,
+    };
+    wrap.clear();
+    for (index, character) in characters.into_iter().enumerate() {
+        frame.placements.push(None);
+        is_newline.push(character == '\n');
+        wrap.process_character(character, index_style(index), context, &mut frame);
+    }
+    wrap.flush(context, &mut frame);
+    let max_line_width = frame.max_line_width;
+    let width = context.size.width() as i32;
+    positions.clear();
+    let mut next = Coord::new(0, 0);
+    for (placement, is_newline) in placements.into_iter().zip(is_newline) {
+        let coord = placement.unwrap_or(next);
+        positions.push(coord);
+        next = if is_newline || coord.x + 1 >= width {
+            Coord::new(0, coord.y + 1)
+        } else {
+            coord + Coord::new(1, 0)
+        };
+    }
+    positions.push(next);
+    max_line_width
+}
+
+/// Lay out text with a wrap without rendering it
+pub fn measure_text<W: Wrap>(wrap: &mut W, text: &str, width: u32) -> TextMeasurement {
+    let context = ViewContext::default_with_size(Size::new(width, Size::max_field()));
+    let mut positions = Vec::new();
+    let max_line_width = wrap_characters(
+        wrap,
+        text.chars(),
+        Style::new(),
+        context,
+        None::<&mut Buffer>,
+        &mut positions,
+    );
+    let mut line_starts = vec![0];
+    let mut y = 0;
+    for ((byte_index, _), coord) in text.char_indices().zip(positions) {
+        if coord.y > y {
+            y = coord.y;
+            line_starts.push(byte_index);
+        }
+    }
+    TextMeasurement {
+        num_lines: wrap.num_lines(),
+        max_line_width,
+        line_starts,
+    }
+}
+
+const DEFAULT_CACHE_CAPACITY: usize = 1024;
+
+/// Remembers the measurements of text at each width it has been measured at,
+/// so that repeatedly laying out the same text is cheap. Measurements are
+/// keyed by a hash of the text, so the text itself isn't stored. Once the
+/// cache holds `capacity` measurements it's emptied before adding another.
+#[derive(Debug, Clone)]
+pub struct TextMeasureCache<W: Wrap> {
+    wrap: W,
+    capacity: usize,
+    measurements: HashMap<(u32, u64), TextMeasurement>,
+}
+
+impl<W: Wrap + Default> Default for TextMeasureCache<W> {
+    fn default() -> Self {
+        Self::new(W::default())
+    }
+}
+
+impl<W: Wrap> TextMeasureCache<W> {
+    pub fn new(wrap: W) -> Self {
+        Self::with_capacity(wrap, DEFAULT_CACHE_CAPACITY)
+    }
+    pub fn with_capacity(wrap: W, capacity: usize) -> Self {
+        Self {
+            wrap,
+            capacity: capacity.max(1),
+            measurements: HashMap::new(),
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.measurements.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.measurements.is_empty()
+    }
+    pub fn measure(&mut self, text: &str, width: u32) -> &TextMeasurement {
+        let mut hasher = DefaultHasher::new();
+        text.hash(&mut hasher);
+        let key = (width, hasher.finish());
+        if !self.measurements.contains_key(&key) && self.measurements.len() >= self.capacity {
+            self.measurements.clear();
+        }
+        let wrap = &mut self.wrap;
+        self.measurements
+            .entry(key)
+            .or_insert_with(|| measure_text(wrap, text, width))
+    }
+    /// Forget the measurements of a text at every width, such as after the
+    /// text has been changed
+    pub fn invalidate(&mut self, text: &str) {
+        let mut hasher = DefaultHasher::new();
+        text.hash(&mut hasher);
+        let hash = hasher.finish();
+        self.measurements.retain(|&(_, h), _| h != hash);
+    }
+    pub fn clear(&mut self) {
+        self.measurements.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::wrap;
+
+    #[test]
+    fn measure_word_wrap() {
+        let mut cache = TextMeasureCache::new(wrap::Word::new());
+        let measurement = cache.measure("aa bb cc\n\ndd", 6).clone();
+        assert_eq!(
+            measurement,
+            TextMeasurement {
+                num_lines: 4,
+                max_line_width: 5,
+                line_starts: vec![0, 6, 9, 10],
+            }
+        );
+        assert_eq!(cache.measure("aa bb cc\n\ndd", 6), &measurement);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn measure_hyphenated_and_justified() {
+        let mut justify = wrap::Justify::new().with_hyphenation(wrap::Hyphenation::new('-'));
+        assert_eq!(
+            measure_text(&mut justify, "a bb abcdefgh", 6),
+            TextMeasurement {
+                num_lines: 3,
+                max_line_width: 6,
+                line_starts: vec![0, 4, 10],
+            }
+        );
+    }
+
+    #[test]
+    fn cache_is_bounded() {
+        let mut cache = TextMeasureCache::with_capacity(wrap::Word::new(), 2);
+        cache.measure("a", 4);
+        cache.measure("b", 4);
+        cache.measure("a", 4);
+        assert_eq!(cache.len(), 2);
+        cache.measure("c", 4);
+        assert_eq!(cache.len(), 1);
+        cache.measure("a", 8);
+        cache.invalidate("a");
+        assert_eq!(cache.len(), 1);
+    }
+}
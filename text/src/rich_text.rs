@@ -1,3 +1,6 @@
+use crate::align::{AlignBuffer, AlignedWrap, TextAlignment};
+use crate::expand::{CharacterExpansion, ExpandedWrap};
+use crate::truncate::{single_line, view_truncated, Truncation};
 use crate::wrap::{self, Wrap};
 use chargrid_render::*;
 #[cfg(feature = "serialize")]
@@ -36,7 +39,10 @@ impl<'a> RichTextPart<'a> {
 }
 
 pub struct RichTextView<W: Wrap> {
+    pub alignment: TextAlignment,
+    pub expansion: CharacterExpansion,
     wrap: W,
+    align_buffer: AlignBuffer,
 }
 
 impl Default for RichTextView<wrap::Word> {
@@ -47,7 +53,29 @@ impl Default for RichTextView<wrap::Word> {
 
 impl<W: Wrap> RichTextView<W> {
     pub fn new(wrap: W) -> Self {
-        Self { wrap }
+        Self {
+            alignment: TextAlignment::Left,
+            expansion: CharacterExpansion::new(),
+            wrap,
+            align_buffer: AlignBuffer::default(),
+        }
+    }
+    pub fn with_alignment(self, alignment: TextAlignment) -> Self {
+        Self { alignment, ..self }
+    }
+    pub fn with_expansion(self, expansion: CharacterExpansion) -> Self {
+        Self { expansion, ..self }
+    }
+    fn expanded_wrap(&mut self) -> ExpandedWrap<'_, W> {
+        ExpandedWrap {
+            wrap: AlignedWrap {
+                wrap: &mut self.wrap,
+                alignment: self.alignment,
+                buffer: &mut self.align_buffer,
+            },
+            expansion: &self.expansion,
+            column: 0,
+        }
     }
 }
 
@@ -57,14 +85,14 @@ where
     W: Wrap,
 {
     fn view<F: Frame, C: ColModify>(&mut self, parts: I, context: ViewContext<C>, frame: &mut F) {
-        self.wrap.clear();
+        let mut wrap = self.expanded_wrap();
+        wrap.clear();
         for part in parts {
             for character in part.text.chars() {
-                self.wrap
-                    .process_character(character, part.style, context, frame);
+                wrap.process_character(character, part.style, context, frame);
             }
         }
-        self.wrap.flush(context, frame);
+        wrap.flush(context, frame);
     }
 }
 
@@ -75,6 +103,9 @@ impl RichTextViewSingleLine {
     pub const fn new() -> Self {
         Self
     }
+    pub fn with_truncation(self, truncation: Truncation) -> TruncatedRichTextView {
+        TruncatedRichTextView::new().with_truncation(truncation)
+    }
 }
 
 impl<'a, I> View<I> for RichTextViewSingleLine
@@ -86,13 +117,74 @@ where
     }
 }
 
+/// Like `RichTextViewSingleLine`, but text which doesn't fit is truncated
+/// according to `truncation`, and characters are expanded according to
+/// `expansion`
+#[derive(Default, Debug, Clone)]
+pub struct TruncatedRichTextView {
+    pub truncation: Truncation,
+    pub expansion: CharacterExpansion,
+}
+
+impl TruncatedRichTextView {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_truncation(self, truncation: Truncation) -> Self {
+        Self { truncation, ..self }
+    }
+    pub fn with_expansion(self, expansion: CharacterExpansion) -> Self {
+        Self { expansion, ..self }
+    }
+}
+
+impl<'a, I> View<I> for TruncatedRichTextView
+where
+    I: IntoIterator<Item = RichTextPart<'a>>,
+{
+    fn view<F: Frame, C: ColModify>(&mut self, parts: I, context: ViewContext<C>, frame: &mut F) {
+        let characters = parts.into_iter().flat_map(|part| {
+            part.text
+                .chars()
+                .map(move |character| (character, part.style))
+        });
+        let line = single_line(characters, &self.expansion);
+        view_truncated(&line, self.truncation, context, frame);
+    }
+}
+
 pub struct RichStringView<W: Wrap> {
+    pub alignment: TextAlignment,
+    pub expansion: CharacterExpansion,
     wrap: W,
+    align_buffer: AlignBuffer,
 }
 
 impl<W: Wrap> RichStringView<W> {
     pub fn new(wrap: W) -> Self {
-        Self { wrap }
+        Self {
+            alignment: TextAlignment::Left,
+            expansion: CharacterExpansion::new(),
+            wrap,
+            align_buffer: AlignBuffer::default(),
+        }
+    }
+    pub fn with_alignment(self, alignment: TextAlignment) -> Self {
+        Self { alignment, ..self }
+    }
+    pub fn with_expansion(self, expansion: CharacterExpansion) -> Self {
+        Self { expansion, ..self }
+    }
+    fn expanded_wrap(&mut self) -> ExpandedWrap<'_, W> {
+        ExpandedWrap {
+            wrap: AlignedWrap {
+                wrap: &mut self.wrap,
+                alignment: self.alignment,
+                buffer: &mut self.align_buffer,
+            },
+            expansion: &self.expansion,
+            column: 0,
+        }
     }
 }
 
@@ -106,13 +198,13 @@ where
         context: ViewContext<C>,
         frame: &mut F,
     ) {
-        self.wrap.clear();
+        let mut wrap = self.expanded_wrap();
+        wrap.clear();
         let part: RichTextPart = part.into();
         for character in part.text.chars() {
-            self.wrap
-                .process_character(character, part.style, context, frame);
+            wrap.process_character(character, part.style, context, frame);
         }
-        self.wrap.flush(context, frame);
+        wrap.flush(context, frame);
     }
 }
 
@@ -123,6 +215,9 @@ impl RichStringViewSingleLine {
     pub fn new() -> Self {
         Self
     }
+    pub fn with_truncation(self, truncation: Truncation) -> TruncatedRichStringView {
+        TruncatedRichStringView::new().with_truncation(truncation)
+    }
 }
 
 impl<'a> View<RichTextPart<'a>> for RichStringViewSingleLine {
@@ -135,3 +230,37 @@ impl<'a> View<RichTextPart<'a>> for RichStringViewSingleLine {
         RichStringView::new(wrap::None::new()).view(part, context, frame);
     }
 }
+
+/// Like `RichStringViewSingleLine`, but text which doesn't fit is truncated
+/// according to `truncation`, and characters are expanded according to
+/// `expansion`
+#[derive(Default, Debug, Clone)]
+pub struct TruncatedRichStringView {
+    pub truncation: Truncation,
+    pub expansion: CharacterExpansion,
+}
+
+impl TruncatedRichStringView {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_truncation(self, truncation: Truncation) -> Self {
+        Self { truncation, ..self }
+    }
+    pub fn with_expansion(self, expansion: CharacterExpansion) -> Self {
+        Self { expansion, ..self }
+    }
+}
+
+impl<'a> View<RichTextPart<'a>> for TruncatedRichStringView {
+    fn view<F: Frame, C: ColModify>(
+        &mut self,
+        part: RichTextPart<'a>,
+        context: ViewContext<C>,
+        frame: &mut F,
+    ) {
+        let characters = part.text.chars().map(|character| (character, part.style));
+        let line = single_line(characters, &self.expansion);
+        view_truncated(&line, self.truncation, context, frame);
+    }
+}
@@ -1,19 +1,48 @@
+use crate::align::{AlignBuffer, AlignedWrap, TextAlignment};
 use crate::default::*;
+use crate::expand::{CharacterExpansion, ExpandedWrap};
+use crate::truncate::{single_line, view_truncated, Truncation};
 use crate::wrap::{self, Wrap};
 use chargrid_render::*;
 
 pub struct TextView<W: Wrap> {
     pub style: Style,
+    pub alignment: TextAlignment,
+    pub expansion: CharacterExpansion,
     wrap: W,
+    align_buffer: AlignBuffer,
 }
 
 impl<W: Wrap> TextView<W> {
     pub fn new(style: Style, wrap: W) -> Self {
-        Self { style, wrap }
+        Self {
+            style,
+            alignment: TextAlignment::Left,
+            expansion: CharacterExpansion::new(),
+            wrap,
+            align_buffer: AlignBuffer::default(),
+        }
     }
     pub fn new_default_style(wrap: W) -> Self {
         Self::new(DEFAULT_STYLE, wrap)
     }
+    pub fn with_alignment(self, alignment: TextAlignment) -> Self {
+        Self { alignment, ..self }
+    }
+    pub fn with_expansion(self, expansion: CharacterExpansion) -> Self {
+        Self { expansion, ..self }
+    }
+    fn expanded_wrap(&mut self) -> ExpandedWrap<'_, W> {
+        ExpandedWrap {
+            wrap: AlignedWrap {
+                wrap: &mut self.wrap,
+                alignment: self.alignment,
+                buffer: &mut self.align_buffer,
+            },
+            expansion: &self.expansion,
+            column: 0,
+        }
+    }
 }
 
 impl<S, I, W> View<I> for TextView<W>
@@ -23,30 +52,57 @@ where
     W: Wrap,
 {
     fn view<F: Frame, C: ColModify>(&mut self, parts: I, context: ViewContext<C>, frame: &mut F) {
-        self.wrap.clear();
+        let style = self.style;
+        let mut wrap = self.expanded_wrap();
+        wrap.clear();
         for part in parts {
             let part = part.as_ref();
             for character in part.chars() {
-                self.wrap
-                    .process_character(character, self.style, context, frame);
+                wrap.process_character(character, style, context, frame);
             }
         }
-        self.wrap.flush(context, frame);
+        wrap.flush(context, frame);
     }
 }
 
 pub struct StringView<W: Wrap> {
     pub style: Style,
+    pub alignment: TextAlignment,
+    pub expansion: CharacterExpansion,
     wrap: W,
+    align_buffer: AlignBuffer,
 }
 
 impl<W: Wrap> StringView<W> {
     pub fn new(style: Style, wrap: W) -> Self {
-        Self { style, wrap }
+        Self {
+            style,
+            alignment: TextAlignment::Left,
+            expansion: CharacterExpansion::new(),
+            wrap,
+            align_buffer: AlignBuffer::default(),
+        }
     }
     pub fn new_default_style(wrap: W) -> Self {
         Self::new(DEFAULT_STYLE, wrap)
     }
+    pub fn with_alignment(self, alignment: TextAlignment) -> Self {
+        Self { alignment, ..self }
+    }
+    pub fn with_expansion(self, expansion: CharacterExpansion) -> Self {
+        Self { expansion, ..self }
+    }
+    fn expanded_wrap(&mut self) -> ExpandedWrap<'_, W> {
+        ExpandedWrap {
+            wrap: AlignedWrap {
+                wrap: &mut self.wrap,
+                alignment: self.alignment,
+                buffer: &mut self.align_buffer,
+            },
+            expansion: &self.expansion,
+            column: 0,
+        }
+    }
 }
 
 impl<'a, S, W> View<S> for StringView<W>
@@ -55,13 +111,14 @@ where
     W: Wrap,
 {
     fn view<F: Frame, C: ColModify>(&mut self, part: S, context: ViewContext<C>, frame: &mut F) {
-        self.wrap.clear();
+        let style = self.style;
+        let mut wrap = self.expanded_wrap();
+        wrap.clear();
         let part = part.as_ref();
         for character in part.chars() {
-            self.wrap
-                .process_character(character, self.style, context, frame);
+            wrap.process_character(character, style, context, frame);
         }
-        self.wrap.flush(context, frame);
+        wrap.flush(context, frame);
     }
 }
 
@@ -82,6 +139,9 @@ impl StringViewSingleLine {
     pub const fn new(style: Style) -> Self {
         Self { style }
     }
+    pub fn with_truncation(self, truncation: Truncation) -> TruncatedStringView {
+        TruncatedStringView::new(self.style).with_truncation(truncation)
+    }
 }
 
 impl<'a, S> View<S> for StringViewSingleLine
@@ -93,9 +153,56 @@ where
     }
 }
 
+/// Like `StringViewSingleLine`, but text which doesn't fit is truncated
+/// according to `truncation`, and characters are expanded according to
+/// `expansion`
+#[derive(Debug, Clone)]
+pub struct TruncatedStringView {
+    pub style: Style,
+    pub truncation: Truncation,
+    pub expansion: CharacterExpansion,
+}
+
+impl Default for TruncatedStringView {
+    fn default() -> Self {
+        Self::new(DEFAULT_STYLE)
+    }
+}
+
+impl TruncatedStringView {
+    pub fn new(style: Style) -> Self {
+        Self {
+            style,
+            truncation: Truncation::Clip,
+            expansion: CharacterExpansion::new(),
+        }
+    }
+    pub fn with_truncation(self, truncation: Truncation) -> Self {
+        Self { truncation, ..self }
+    }
+    pub fn with_expansion(self, expansion: CharacterExpansion) -> Self {
+        Self { expansion, ..self }
+    }
+}
+
+impl<S> View<S> for TruncatedStringView
+where
+    S: AsRef<str>,
+{
+    fn view<F: Frame, C: ColModify>(&mut self, part: S, context: ViewContext<C>, frame: &mut F) {
+        let style = self.style;
+        let line = single_line(
+            part.as_ref().chars().map(|character| (character, style)),
+            &self.expansion,
+        );
+        view_truncated(&line, self.truncation, context, frame);
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::expand::{ControlCharacters, TabExpansion};
 
     #[test]
     fn word_wrap_very_wide_context() {
@@ -130,4 +237,181 @@ mod test {
             ]
         );
     }
+
+    fn render_rows<W: Wrap>(wrap: W, width: u32, text: &str) -> Vec<String> {
+        render_rows_aligned(wrap, TextAlignment::Left, width, text)
+    }
+
+    fn render_rows_aligned<W: Wrap>(
+        wrap: W,
+        alignment: TextAlignment,
+        width: u32,
+        text: &str,
+    ) -> Vec<String> {
+        let mut test_grid = chargrid_test_grid::TestGrid::new(Size::new(width, 4));
+        let context = ViewContext::default_with_size(Size::new(width, 4));
+        TextView::new_default_style(wrap)
+            .with_alignment(alignment)
+            .view(&[text], context, &mut test_grid);
+        test_grid.string_rows()
+    }
+
+    #[test]
+    fn justify() {
+        assert_eq!(
+            render_rows(wrap::Justify::new(), 12, "a bb ccc dddd ee\nf g"),
+            &[
+                "a   bb   ccc",
+                "dddd ee     ",
+                "f g         ",
+                "            ",
+            ]
+        );
+    }
+
+    #[test]
+    fn line_break_after_hyphen() {
+        assert_eq!(
+            render_rows(wrap::LineBreak::new(), 10, "a well-known (word)"),
+            &["a well-   ", "known     ", "(word)    ", "          ",]
+        );
+    }
+
+    #[test]
+    fn hyphenate_long_words() {
+        let wrap = wrap::LineBreak::new().with_hyphenation(wrap::Hyphenation::new('~'));
+        assert_eq!(
+            render_rows(wrap, 8, "the extraordinary cat"),
+            &["the ext~", "raordin~", "ary cat ", "        "]
+        );
+    }
+
+    #[test]
+    fn align_centre_and_right() {
+        assert_eq!(
+            render_rows_aligned(wrap::Word::new(), TextAlignment::Centre, 9, "ab cdef gh"),
+            &[" ab cdef ", "   gh    ", "         ", "         "]
+        );
+        assert_eq!(
+            render_rows_aligned(wrap::Char::new(), TextAlignment::Right, 6, "abcdefgh"),
+            &["abcdef", "    gh", "      ", "      "]
+        );
+    }
+
+    #[test]
+    fn align_justify_any_wrap() {
+        assert_eq!(
+            render_rows_aligned(
+                wrap::Word::new(),
+                TextAlignment::Justify,
+                10,
+                "a bb ccc dd\ne f"
+            ),
+            &["a  bb  ccc", "dd        ", "e f       ", "          "]
+        );
+    }
+
+    #[test]
+    fn align_justify_matches_justify_wrap() {
+        let text = "a bb cc ddd\ne f";
+        let expected = &["a   bb  cc", "ddd       ", "e f       ", "          "];
+        assert_eq!(render_rows(wrap::Justify::new(), 10, text), expected);
+        assert_eq!(
+            render_rows_aligned(wrap::Word::new(), TextAlignment::Justify, 10, text),
+            expected
+        );
+        assert_eq!(
+            render_rows_aligned(wrap::Justify::new(), TextAlignment::Justify, 10, text),
+            expected
+        );
+    }
+
+    #[test]
+    fn truncation() {
+        let render = |truncation| {
+            let mut test_grid = chargrid_test_grid::TestGrid::new(Size::new(5, 1));
+            let context = ViewContext::default_with_size(Size::new(5, 1));
+            StringViewSingleLine::default()
+                .with_truncation(truncation)
+                .view("abcdefgh", context, &mut test_grid);
+            test_grid.string_rows()
+        };
+        assert_eq!(render(Truncation::EndEllipsis), &["abcd…"]);
+        assert_eq!(render(Truncation::StartEllipsis), &["…efgh"]);
+        assert_eq!(render(Truncation::MiddleElision), &["ab…gh"]);
+        assert_eq!(render(Truncation::Clip), &["abcde"]);
+    }
+
+    #[test]
+    fn truncation_expands_characters() {
+        let render = |expansion, text| {
+            let mut test_grid = chargrid_test_grid::TestGrid::new(Size::new(5, 1));
+            let context = ViewContext::default_with_size(Size::new(5, 1));
+            TruncatedStringView::default()
+                .with_truncation(Truncation::EndEllipsis)
+                .with_expansion(expansion)
+                .view(text, context, &mut test_grid);
+            test_grid.string_rows()
+        };
+        let tabs = CharacterExpansion::new().with_tabs(TabExpansion::Width(4));
+        assert_eq!(render(tabs, "a\tbcdef"), &["a   …"]);
+        assert_eq!(render(CharacterExpansion::new(), "xxxxxxx\rab"), &["abxx…"]);
+    }
+
+    #[test]
+    fn fade_out_blends_background() {
+        let mut buffer = Buffer::new(Size::new(4, 1));
+        let context = ViewContext::default_with_size(buffer.size());
+        let white = Rgb24::new_grey(255);
+        let black = Rgb24::new_grey(0);
+        TruncatedStringView::new(Style::new().with_foreground(white).with_background(white))
+            .with_truncation(Truncation::FadeOut {
+                cells: 2,
+                to: black,
+            })
+            .view("abcdefgh", context, &mut buffer);
+        let cells = buffer.iter().collect::<Vec<_>>();
+        assert_eq!(cells[1].background_colour, white);
+        assert!(cells[3].background_colour.r < cells[2].background_colour.r);
+        assert!(cells[2].background_colour.r < 255);
+        assert_eq!(cells[3].foreground_colour, cells[3].background_colour);
+    }
+
+    #[test]
+    fn tabs_and_control_characters() {
+        let render = |expansion| {
+            let mut test_grid = chargrid_test_grid::TestGrid::new(Size::new(10, 2));
+            let context = ViewContext::default_with_size(Size::new(10, 2));
+            TextView::new_default_style(wrap::Word::new())
+                .with_expansion(expansion)
+                .view(&["a\tbc\td\n\u{1b}x\ty"], context, &mut test_grid);
+            test_grid.string_rows()
+        };
+        let expansion = CharacterExpansion::new()
+            .with_tabs(TabExpansion::Width(4))
+            .with_control_characters(ControlCharacters::Caret);
+        assert_eq!(render(expansion.clone()), &["a   bc  d ", "^[x y     "]);
+        assert_eq!(
+            render(
+                expansion
+                    .with_tabs(TabExpansion::Stops(vec![2, 5]))
+                    .with_visible_whitespace(true)
+            ),
+            &["a→bc→d    ", "^[x→·y    "]
+        );
+    }
+
+    #[test]
+    fn tab_stops_follow_wrapped_rows() {
+        let render = |tabs| {
+            let mut test_grid = chargrid_test_grid::TestGrid::new(Size::new(6, 2));
+            let context = ViewContext::default_with_size(Size::new(6, 2));
+            TextView::new_default_style(wrap::Char::new())
+                .with_expansion(CharacterExpansion::new().with_tabs(tabs))
+                .view(&["abcdefg\tx"], context, &mut test_grid);
+            test_grid.string_rows()
+        };
+        assert_eq!(render(TabExpansion::Width(4)), &["abcdef", "g   x "]);
+        assert_eq!(render(TabExpansion::Width(0)), &["abcdef", "g x   "]);
+    }
 }
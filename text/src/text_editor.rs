@@ -0,0 +1,691 @@
+use crate::default::*;
+use crate::measure::wrap_characters;
+use crate::wrap::Wrap;
+use chargrid_decorator::{VerticalScrollLimits, VerticalScrollState};
+use chargrid_input::{keys, Input, KeyboardInput, MouseButton, MouseInput, ScrollDirection};
+use chargrid_render::*;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+pub mod editor_keys {
+    use chargrid_input::KeyboardInput;
+
+    pub const SELECT_ALL: KeyboardInput = KeyboardInput::Char('\u{1}');
+    /// Only handled while text is selected, so that apps may still use ctrl-c
+    /// to quit
+    pub const COPY: KeyboardInput = KeyboardInput::Char('\u{3}');
+    pub const PASTE: KeyboardInput = KeyboardInput::Char('\u{16}');
+    pub const CUT: KeyboardInput = KeyboardInput::Char('\u{18}');
+    pub const REDO: KeyboardInput = KeyboardInput::Char('\u{19}');
+    pub const UNDO: KeyboardInput = KeyboardInput::Char('\u{1a}');
+}
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy)]
+pub struct TextEditorStyle {
+    pub text: Style,
+    pub cursor: Style,
+    pub selection: Style,
+}
+
+impl TextEditorStyle {
+    pub fn new() -> Self {
+        Self {
+            text: DEFAULT_STYLE,
+            cursor: Style::new()
+                .with_foreground(Rgb24::new_grey(0))
+                .with_background(Rgb24::new_grey(255)),
+            selection: Style::new().with_background(Rgb24::new(0, 63, 127)),
+        }
+    }
+}
+
+impl Default for TextEditorStyle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Where each character of a `TextEditor` was drawn, as of the last render.
+/// This is used to navigate between soft-wrapped rows and to find the
+/// character under the mouse.
+#[derive(Debug, Clone, Default)]
+pub struct TextEditorLayout {
+    last_offset: Coord,
+    last_size: Size,
+    /// The position of each character, followed by the position just after
+    /// the last character
+    positions: Vec<Coord>,
+}
+
+impl TextEditorLayout {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn num_rows(&self) -> u32 {
+        self.positions
+            .last()
+            .map(|coord| coord.y as u32 + 1)
+            .unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextEditorEvent {
+    /// The text changed
+    Edited,
+    /// The cursor, selection or scroll position changed
+    Moved,
+    /// The input had no effect
+    Ignored,
+}
+
+/// A change to the text which can be undone, replacing `removed` with
+/// `inserted` at `start`
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+struct Edit {
+    start: usize,
+    removed: Vec<char>,
+    inserted: Vec<char>,
+    /// The cursor before the edit
+    cursor: usize,
+}
+
+/// Multi-line editable text with a selection, an internal clipboard and an
+/// undo history. Positions are measured in characters.
+///
+/// Since inputs don't carry modifier keys, extending the selection from the
+/// keyboard (e.g. with shift+arrows) and moving by words have no default
+/// bindings in `handle_input`. The corresponding methods take a `select`
+/// argument and may be bound to keys of the app's choosing. The mouse can
+/// select text by dragging.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default)]
+pub struct TextEditor {
+    text: Vec<char>,
+    cursor: usize,
+    anchor: Option<usize>,
+    goal_x: Option<i32>,
+    clipboard: String,
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    typing: bool,
+    dragging: bool,
+}
+
+fn is_word_char(character: char) -> bool {
+    character.is_alphanumeric() || character == '_'
+}
+
+/// Positions of characters as if they were drawn without wrapping
+fn logical_positions(text: &[char]) -> Vec<Coord> {
+    let mut coord = Coord::new(0, 0);
+    let mut positions = Vec::with_capacity(text.len() + 1);
+    for &character in text {
+        positions.push(coord);
+        if character == '\n' {
+            coord = Coord::new(0, coord.y + 1);
+        } else {
+            coord.x += 1;
+        }
+    }
+    positions.push(coord);
+    positions
+}
+
+/// The index of the character in row `y` closest to column `x`
+fn index_in_row(positions: &[Coord], x: i32, y: i32) -> usize {
+    if y < 0 {
+        return 0;
+    }
+    let mut first_in_row = None;
+    let mut best = None;
+    for (i, coord) in positions.iter().enumerate() {
+        if coord.y == y {
+            first_in_row.get_or_insert(i);
+            if coord.x <= x {
+                best = Some(i);
+            }
+        } else if coord.y > y {
+            break;
+        }
+    }
+    best.or(first_in_row).unwrap_or(positions.len() - 1)
+}
+
+impl TextEditor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_text(mut self, text: &str) -> Self {
+        self.set_text(text);
+        self
+    }
+    pub fn text(&self) -> String {
+        self.text.iter().collect()
+    }
+    /// Replace the text, moving the cursor to the end and clearing the undo
+    /// history
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.chars().collect();
+        self.cursor = self.text.len();
+        self.anchor = None;
+        self.goal_x = None;
+        self.undo.clear();
+        self.redo.clear();
+        self.typing = false;
+    }
+    pub fn len(&self) -> usize {
+        self.text.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+    pub fn selection(&self) -> Option<Range<usize>> {
+        self.anchor.map(|anchor| {
+            if anchor < self.cursor {
+                anchor..self.cursor
+            } else {
+                self.cursor..anchor
+            }
+        })
+    }
+    pub fn selected_text(&self) -> Option<String> {
+        self.selection()
+            .map(|selection| self.text[selection].iter().collect())
+    }
+    pub fn clipboard(&self) -> &str {
+        &self.clipboard
+    }
+    pub fn set_clipboard<S: Into<String>>(&mut self, clipboard: S) {
+        self.clipboard = clipboard.into();
+    }
+
+    fn positions(&self, layout: &TextEditorLayout) -> Vec<Coord> {
+        if layout.positions.len() == self.text.len() + 1 {
+            layout.positions.clone()
+        } else {
+            logical_positions(&self.text)
+        }
+    }
+
+    /// Move the cursor to the character at `index`. If `select` is true, the
+    /// selection is extended to the new cursor position, otherwise it is
+    /// cleared.
+    pub fn move_to(&mut self, index: usize, select: bool) {
+        let index = index.min(self.text.len());
+        if select {
+            self.anchor.get_or_insert(self.cursor);
+        } else {
+            self.anchor = None;
+        }
+        self.cursor = index;
+        if self.anchor == Some(self.cursor) {
+            self.anchor = None;
+        }
+        self.typing = false;
+    }
+    pub fn move_left(&mut self, select: bool) {
+        self.goal_x = None;
+        match self.selection() {
+            Some(selection) if !select => self.move_to(selection.start, false),
+            _ => self.move_to(self.cursor.saturating_sub(1), select),
+        }
+    }
+    pub fn move_right(&mut self, select: bool) {
+        self.goal_x = None;
+        match self.selection() {
+            Some(selection) if !select => self.move_to(selection.end, false),
+            _ => self.move_to(self.cursor + 1, select),
+        }
+    }
+    /// Move to the start of the current or previous word
+    pub fn move_word_left(&mut self, select: bool) {
+        self.goal_x = None;
+        let mut index = self.cursor;
+        while index > 0 && !is_word_char(self.text[index - 1]) {
+            index -= 1;
+        }
+        while index > 0 && is_word_char(self.text[index - 1]) {
+            index -= 1;
+        }
+        self.move_to(index, select);
+    }
+    /// Move to the end of the current or next word
+    pub fn move_word_right(&mut self, select: bool) {
+        self.goal_x = None;
+        let len = self.text.len();
+        let mut index = self.cursor;
+        while index < len && !is_word_char(self.text[index]) {
+            index += 1;
+        }
+        while index < len && is_word_char(self.text[index]) {
+            index += 1;
+        }
+        self.move_to(index, select);
+    }
+    /// Move up or down by a number of rows, as laid out by the last render,
+    /// staying as close as possible to the original column
+    pub fn move_rows(&mut self, rows: i32, select: bool, layout: &TextEditorLayout) {
+        let positions = self.positions(layout);
+        let current = positions[self.cursor];
+        let goal_x = *self.goal_x.get_or_insert(current.x);
+        let y = current.y + rows;
+        let index = if y > positions[positions.len() - 1].y {
+            self.text.len()
+        } else {
+            index_in_row(&positions, goal_x, y)
+        };
+        self.move_to(index, select);
+        self.goal_x = Some(goal_x);
+    }
+    pub fn move_up(&mut self, select: bool, layout: &TextEditorLayout) {
+        self.move_rows(-1, select, layout);
+    }
+    pub fn move_down(&mut self, select: bool, layout: &TextEditorLayout) {
+        self.move_rows(1, select, layout);
+    }
+    /// Move to the start of the current row
+    pub fn move_row_start(&mut self, select: bool, layout: &TextEditorLayout) {
+        self.goal_x = None;
+        let positions = self.positions(layout);
+        let index = index_in_row(&positions, 0, positions[self.cursor].y);
+        self.move_to(index, select);
+    }
+    /// Move to the end of the current row
+    pub fn move_row_end(&mut self, select: bool, layout: &TextEditorLayout) {
+        self.goal_x = None;
+        let positions = self.positions(layout);
+        let index = index_in_row(&positions, i32::MAX, positions[self.cursor].y);
+        self.move_to(index, select);
+    }
+    pub fn select_all(&mut self) {
+        self.move_to(0, false);
+        self.move_to(self.text.len(), true);
+    }
+
+    /// Replace a range of the text, moving the cursor to the end of the
+    /// inserted characters and recording the change so it can be undone.
+    /// Consecutive typed characters are undone together.
+    fn replace(&mut self, range: Range<usize>, inserted: Vec<char>, typing: bool) {
+        let cursor = self.cursor;
+        let start = range.start;
+        let removed = self
+            .text
+            .splice(range, inserted.iter().cloned())
+            .collect::<Vec<_>>();
+        self.cursor = start + inserted.len();
+        match self.undo.last_mut() {
+            Some(edit)
+                if typing
+                    && self.typing
+                    && removed.is_empty()
+                    && edit.start + edit.inserted.len() == start =>
+            {
+                edit.inserted.extend(inserted)
+            }
+            _ => self.undo.push(Edit {
+                start,
+                removed,
+                inserted,
+                cursor,
+            }),
+        }
+        self.anchor = None;
+        self.redo.clear();
+        self.goal_x = None;
+        self.typing = typing;
+    }
+    /// The selection, or the cursor if nothing is selected
+    fn selection_or_cursor(&self) -> Range<usize> {
+        self.selection().unwrap_or(self.cursor..self.cursor)
+    }
+    /// Insert a character at the cursor, replacing the selection
+    pub fn insert(&mut self, character: char) {
+        let typing = self.anchor.is_none() && character != '\n';
+        self.replace(self.selection_or_cursor(), vec![character], typing);
+    }
+    /// Insert a string at the cursor, replacing the selection
+    pub fn insert_str(&mut self, string: &str) {
+        self.replace(self.selection_or_cursor(), string.chars().collect(), false);
+    }
+    /// Remove the selection, or the character before the cursor
+    pub fn backspace(&mut self) -> bool {
+        let range = match self.selection() {
+            Some(selection) => selection,
+            None if self.cursor == 0 => return false,
+            None => (self.cursor - 1)..self.cursor,
+        };
+        self.replace(range, Vec::new(), false);
+        true
+    }
+    /// Remove the selection, or the character under the cursor
+    pub fn delete(&mut self) -> bool {
+        let range = match self.selection() {
+            Some(selection) => selection,
+            None if self.cursor == self.text.len() => return false,
+            None => self.cursor..(self.cursor + 1),
+        };
+        self.replace(range, Vec::new(), false);
+        true
+    }
+    pub fn copy(&mut self) -> bool {
+        match self.selected_text() {
+            Some(selected_text) => {
+                self.clipboard = selected_text;
+                true
+            }
+            None => false,
+        }
+    }
+    pub fn cut(&mut self) -> bool {
+        self.copy() && self.delete()
+    }
+    pub fn paste(&mut self) -> bool {
+        if self.clipboard.is_empty() {
+            return false;
+        }
+        let clipboard = self.clipboard.clone();
+        self.insert_str(&clipboard);
+        true
+    }
+    fn after_undo_or_redo(&mut self) {
+        self.anchor = None;
+        self.goal_x = None;
+        self.typing = false;
+    }
+    pub fn undo(&mut self) -> bool {
+        match self.undo.pop() {
+            Some(edit) => {
+                let end = edit.start + edit.inserted.len();
+                self.text
+                    .splice(edit.start..end, edit.removed.iter().cloned());
+                self.cursor = edit.cursor;
+                self.redo.push(edit);
+                self.after_undo_or_redo();
+                true
+            }
+            None => false,
+        }
+    }
+    pub fn redo(&mut self) -> bool {
+        match self.redo.pop() {
+            Some(edit) => {
+                let end = edit.start + edit.removed.len();
+                self.text
+                    .splice(edit.start..end, edit.inserted.iter().cloned());
+                self.cursor = edit.start + edit.inserted.len();
+                self.undo.push(edit);
+                self.after_undo_or_redo();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Scroll such that the row containing the cursor is visible. This is
+    /// done by `handle_input`, but since the layout of an edit isn't known
+    /// until the next render, apps may also wish to call this after rendering.
+    pub fn scroll_to_cursor(
+        &self,
+        layout: &TextEditorLayout,
+        scroll_state: &mut VerticalScrollState,
+        scroll_limits: VerticalScrollLimits,
+    ) {
+        let row = layout
+            .positions
+            .get(self.cursor)
+            .or_else(|| layout.positions.last())
+            .map(|coord| coord.y)
+            .unwrap_or(0);
+        scroll_state.scroll_to_show_line(row.max(0) as u32, scroll_limits);
+    }
+
+    fn index_from_screen_coord(
+        &self,
+        coord: Coord,
+        layout: &TextEditorLayout,
+        scroll_state: VerticalScrollState,
+    ) -> usize {
+        let positions = self.positions(layout);
+        let relative =
+            coord - layout.last_offset + Coord::new(0, scroll_state.scroll_position() as i32);
+        if relative.y > positions[positions.len() - 1].y {
+            self.text.len()
+        } else {
+            index_in_row(&positions, relative.x, relative.y)
+        }
+    }
+
+    pub fn handle_input(
+        &mut self,
+        input: Input,
+        layout: &TextEditorLayout,
+        scroll_state: &mut VerticalScrollState,
+        scroll_limits: VerticalScrollLimits,
+    ) -> TextEditorEvent {
+        let event = match input {
+            Input::Keyboard(keyboard_input) => {
+                let edited = match keyboard_input {
+                    KeyboardInput::Left => {
+                        self.move_left(false);
+                        false
+                    }
+                    KeyboardInput::Right => {
+                        self.move_right(false);
+                        false
+                    }
+                    KeyboardInput::Up => {
+                        self.move_up(false, layout);
+                        false
+                    }
+                    KeyboardInput::Down => {
+                        self.move_down(false, layout);
+                        false
+                    }
+                    KeyboardInput::Home => {
+                        self.move_row_start(false, layout);
+                        false
+                    }
+                    KeyboardInput::End => {
+                        self.move_row_end(false, layout);
+                        false
+                    }
+                    KeyboardInput::Delete => self.delete(),
+                    keys::BACKSPACE => self.backspace(),
+                    keys::RETURN => {
+                        self.insert('\n');
+                        true
+                    }
+                    keys::ESCAPE if self.anchor.is_some() => {
+                        self.anchor = None;
+                        false
+                    }
+                    editor_keys::SELECT_ALL => {
+                        self.select_all();
+                        false
+                    }
+                    editor_keys::COPY if self.anchor.is_some() => {
+                        self.copy();
+                        false
+                    }
+                    editor_keys::CUT => self.cut(),
+                    editor_keys::PASTE => self.paste(),
+                    editor_keys::UNDO => self.undo(),
+                    editor_keys::REDO => self.redo(),
+                    KeyboardInput::Char(character) if !character.is_control() => {
+                        self.insert(character);
+                        true
+                    }
+                    _ => return TextEditorEvent::Ignored,
+                };
+                if edited {
+                    TextEditorEvent::Edited
+                } else {
+                    TextEditorEvent::Moved
+                }
+            }
+            Input::Mouse(MouseInput::MousePress {
+                button: MouseButton::Left,
+                coord,
+            }) => {
+                if !(coord - layout.last_offset).is_valid(layout.last_size) {
+                    return TextEditorEvent::Ignored;
+                }
+                let index = self.index_from_screen_coord(coord, layout, *scroll_state);
+                self.goal_x = None;
+                self.move_to(index, false);
+                self.dragging = true;
+                TextEditorEvent::Moved
+            }
+            Input::Mouse(MouseInput::MouseMove {
+                button: Some(MouseButton::Left),
+                coord,
+            }) if self.dragging => {
+                let index = self.index_from_screen_coord(coord, layout, *scroll_state);
+                self.goal_x = None;
+                self.move_to(index, true);
+                TextEditorEvent::Moved
+            }
+            Input::Mouse(MouseInput::MouseRelease { .. }) if self.dragging => {
+                self.dragging = false;
+                return TextEditorEvent::Moved;
+            }
+            Input::Mouse(MouseInput::MouseScroll { direction, .. }) => {
+                match direction {
+                    ScrollDirection::Up => scroll_state.scroll_up_line(scroll_limits),
+                    ScrollDirection::Down => scroll_state.scroll_down_line(scroll_limits),
+                    _ => return TextEditorEvent::Ignored,
+                }
+                return TextEditorEvent::Moved;
+            }
+            _ => return TextEditorEvent::Ignored,
+        };
+        self.scroll_to_cursor(layout, scroll_state, scroll_limits);
+        event
+    }
+}
+
+/// Renders a `TextEditor`, soft-wrapping its text with `wrap`. To scroll
+/// vertically, wrap this in a `VerticalScrollView` and pass the scroll state
+/// to `TextEditor::handle_input`.
+pub struct TextEditorView<'s, 'l, W: Wrap> {
+    pub wrap: W,
+    pub style: &'s TextEditorStyle,
+    pub layout: &'l mut TextEditorLayout,
+}
+
+impl<'s, 'l, 'a, W: Wrap> View<&'a TextEditor> for TextEditorView<'s, 'l, W> {
+    fn view<F: Frame, C: ColModify>(
+        &mut self,
+        text_editor: &'a TextEditor,
+        context: ViewContext<C>,
+        frame: &mut F,
+    ) {
+        let layout = &mut *self.layout;
+        layout.last_offset = context.offset;
+        layout.last_size = context.size;
+        wrap_characters(
+            &mut self.wrap,
+            text_editor.text.iter().cloned(),
+            self.style.text,
+            context,
+            Some(&mut *frame),
+            &mut layout.positions,
+        );
+        let character_at = |index: usize| match text_editor.text.get(index) {
+            Some('\n') | None => ' ',
+            Some(&character) => character,
+        };
+        if let Some(selection) = text_editor.selection() {
+            let style = self.style.selection.coalesce(self.style.text);
+            for index in selection {
+                let view_cell = ViewCell::new()
+                    .with_character(character_at(index))
+                    .with_style(style);
+                frame.set_cell_relative(layout.positions[index], 1, view_cell, context);
+            }
+        }
+        let view_cell = ViewCell::new()
+            .with_character(character_at(text_editor.cursor))
+            .with_style(self.style.cursor.coalesce(self.style.text));
+        frame.set_cell_relative(layout.positions[text_editor.cursor], 2, view_cell, context);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::wrap;
+
+    #[test]
+    fn wrapped_navigation_and_undo() {
+        let mut test_grid = chargrid_test_grid::TestGrid::new(Size::new(6, 3));
+        let context = ViewContext::default_with_size(Size::new(6, 3));
+        let style = TextEditorStyle::new();
+        let mut layout = TextEditorLayout::new();
+        let mut text_editor = TextEditor::new().with_text("aa bb cc\ndd");
+        TextEditorView {
+            wrap: wrap::Word::new(),
+            style: &style,
+            layout: &mut layout,
+        }
+        .view(&text_editor, context, &mut test_grid);
+        assert_eq!(test_grid.string_rows(), &["aa bb ", "cc    ", "dd    "]);
+        assert_eq!(layout.num_rows(), 3);
+        text_editor.move_to(4, false);
+        text_editor.move_down(false, &layout);
+        assert_eq!(text_editor.cursor(), 8);
+        text_editor.move_up(true, &layout);
+        assert_eq!(text_editor.selected_text().as_deref(), Some("b cc"));
+        text_editor.cut();
+        text_editor.insert('x');
+        text_editor.insert('y');
+        assert_eq!(text_editor.text(), "aa bxy\ndd");
+        text_editor.undo();
+        assert_eq!(text_editor.text(), "aa b\ndd");
+        text_editor.undo();
+        assert_eq!(text_editor.text(), "aa bb cc\ndd");
+        text_editor.redo();
+        text_editor.paste();
+        assert_eq!(text_editor.text(), "aa bb cc\ndd");
+    }
+
+    #[test]
+    fn mouse_presses_outside_the_view_are_ignored() {
+        let mut test_grid = chargrid_test_grid::TestGrid::new(Size::new(10, 4));
+        let context = ViewContext {
+            offset: Coord::new(2, 1),
+            ..ViewContext::default_with_size(Size::new(6, 2))
+        };
+        let style = TextEditorStyle::new();
+        let mut layout = TextEditorLayout::new();
+        let mut text_editor = TextEditor::new().with_text("aaaaa bbbbbbb");
+        TextEditorView {
+            wrap: wrap::Justify::new().with_hyphenation(wrap::Hyphenation::new('-')),
+            style: &style,
+            layout: &mut layout,
+        }
+        .view(&text_editor, context, &mut test_grid);
+        let mut scroll_state = VerticalScrollState::new();
+        let scroll_limits = VerticalScrollLimits::new();
+        let mut press = |text_editor: &mut TextEditor, x, y| {
+            let input = Input::Mouse(MouseInput::MousePress {
+                button: MouseButton::Left,
+                coord: Coord::new(x, y),
+            });
+            text_editor.handle_input(input, &layout, &mut scroll_state, scroll_limits)
+        };
+        assert_eq!(press(&mut text_editor, 0, 0), TextEditorEvent::Ignored);
+        assert_eq!(text_editor.cursor(), 13);
+        assert_eq!(press(&mut text_editor, 3, 2), TextEditorEvent::Moved);
+        assert_eq!(text_editor.cursor(), 7);
+        assert_eq!(press(&mut text_editor, 8, 1), TextEditorEvent::Ignored);
+        assert_eq!(text_editor.cursor(), 7);
+    }
+}
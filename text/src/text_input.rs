@@ -0,0 +1,335 @@
+use crate::default::*;
+use chargrid_event_routine::{event_or_peek_with_handled, EventOrPeek, EventRoutine, Handled};
+use chargrid_input::{keys, Input, KeyboardInput};
+use chargrid_render::*;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy)]
+pub struct TextInputStyle {
+    pub text: Style,
+    pub placeholder: Style,
+    pub cursor: Style,
+}
+
+impl TextInputStyle {
+    pub fn new() -> Self {
+        Self {
+            text: DEFAULT_STYLE,
+            placeholder: Style::new().with_foreground(Rgb24::new_grey(127)),
+            cursor: Style::new()
+                .with_foreground(Rgb24::new_grey(0))
+                .with_background(Rgb24::new_grey(255)),
+        }
+    }
+}
+
+impl Default for TextInputStyle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The result of passing an input to a `TextInput`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextInputEvent {
+    /// The text or cursor position changed
+    Edited,
+    /// The input had no effect
+    Ignored,
+    Submit,
+    Cancel,
+}
+
+/// An editable single line of text. Positions are measured in characters
+/// rather than bytes.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default)]
+pub struct TextInput {
+    text: String,
+    cursor: usize,
+    max_length: Option<usize>,
+    placeholder: String,
+    mask: Option<char>,
+}
+
+impl TextInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_text<S: Into<String>>(mut self, text: S) -> Self {
+        self.set_text(text);
+        self
+    }
+    /// Limit the number of characters which may be entered
+    pub fn with_max_length(self, max_length: usize) -> Self {
+        Self {
+            max_length: Some(max_length),
+            ..self
+        }
+    }
+    /// Text displayed while the input is empty
+    pub fn with_placeholder<S: Into<String>>(self, placeholder: S) -> Self {
+        Self {
+            placeholder: placeholder.into(),
+            ..self
+        }
+    }
+    /// Display each character of the text as `mask`, for entering passwords
+    pub fn with_mask(self, mask: char) -> Self {
+        Self {
+            mask: Some(mask),
+            ..self
+        }
+    }
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+    pub fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+    /// Replace the text, truncating it to the maximum length, and move the
+    /// cursor to the end
+    pub fn set_text<S: Into<String>>(&mut self, text: S) {
+        self.text = text.into();
+        if let Some(max_length) = self.max_length {
+            let end = self.byte_index(max_length);
+            self.text.truncate(end);
+        }
+        self.cursor = self.len();
+    }
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.cursor = 0;
+    }
+    fn byte_index(&self, char_index: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(char_index)
+            .map(|(i, _)| i)
+            .unwrap_or_else(|| self.text.len())
+    }
+    /// Insert a character at the cursor. Returns false if the text is
+    /// already at its maximum length.
+    pub fn insert(&mut self, character: char) -> bool {
+        if let Some(max_length) = self.max_length {
+            if self.len() >= max_length {
+                return false;
+            }
+        }
+        let index = self.byte_index(self.cursor);
+        self.text.insert(index, character);
+        self.cursor += 1;
+        true
+    }
+    /// Remove the character before the cursor
+    pub fn backspace(&mut self) -> bool {
+        if self.cursor == 0 {
+            return false;
+        }
+        self.cursor -= 1;
+        let index = self.byte_index(self.cursor);
+        self.text.remove(index);
+        true
+    }
+    /// Remove the character under the cursor
+    pub fn delete(&mut self) -> bool {
+        if self.cursor >= self.len() {
+            return false;
+        }
+        let index = self.byte_index(self.cursor);
+        self.text.remove(index);
+        true
+    }
+    pub fn set_cursor(&mut self, cursor: usize) {
+        self.cursor = cursor.min(self.len());
+    }
+    pub fn move_left(&mut self) -> bool {
+        let cursor = self.cursor;
+        self.set_cursor(cursor.saturating_sub(1));
+        self.cursor != cursor
+    }
+    pub fn move_right(&mut self) -> bool {
+        let cursor = self.cursor;
+        self.set_cursor(cursor + 1);
+        self.cursor != cursor
+    }
+    pub fn move_home(&mut self) -> bool {
+        let cursor = self.cursor;
+        self.set_cursor(0);
+        self.cursor != cursor
+    }
+    pub fn move_end(&mut self) -> bool {
+        let cursor = self.cursor;
+        self.set_cursor(self.len());
+        self.cursor != cursor
+    }
+    pub fn handle_input(&mut self, input: Input) -> TextInputEvent {
+        let edited = match input {
+            Input::Keyboard(keys::RETURN) => return TextInputEvent::Submit,
+            Input::Keyboard(keys::ESCAPE) => return TextInputEvent::Cancel,
+            Input::Keyboard(keys::BACKSPACE) => self.backspace(),
+            Input::Keyboard(KeyboardInput::Delete) => self.delete(),
+            Input::Keyboard(KeyboardInput::Left) => self.move_left(),
+            Input::Keyboard(KeyboardInput::Right) => self.move_right(),
+            Input::Keyboard(KeyboardInput::Home) => self.move_home(),
+            Input::Keyboard(KeyboardInput::End) => self.move_end(),
+            Input::Keyboard(KeyboardInput::Char(character)) if !character.is_control() => {
+                self.insert(character)
+            }
+            _ => false,
+        };
+        if edited {
+            TextInputEvent::Edited
+        } else {
+            TextInputEvent::Ignored
+        }
+    }
+}
+
+/// Renders a `TextInput` on a single line, scrolling horizontally to keep
+/// the cursor visible when the text is wider than the view
+#[derive(Debug, Clone, Default)]
+pub struct TextInputView {
+    pub style: TextInputStyle,
+    scroll: usize,
+}
+
+impl TextInputView {
+    pub fn new(style: TextInputStyle) -> Self {
+        Self { style, scroll: 0 }
+    }
+    /// Index of the first visible character as of the last render
+    pub fn scroll(&self) -> usize {
+        self.scroll
+    }
+}
+
+impl<'a> View<&'a TextInput> for TextInputView {
+    fn view<F: Frame, C: ColModify>(
+        &mut self,
+        text_input: &'a TextInput,
+        context: ViewContext<C>,
+        frame: &mut F,
+    ) {
+        let width = context.size.width() as usize;
+        if width == 0 {
+            return;
+        }
+        // leave room for the cursor past the end of the text
+        self.scroll = self
+            .scroll
+            .min(text_input.cursor)
+            .max((text_input.cursor + 1).saturating_sub(width))
+            .min(text_input.len().saturating_sub(width - 1));
+        let (characters, style, scroll) = if text_input.text.is_empty() {
+            (text_input.placeholder.as_str(), self.style.placeholder, 0)
+        } else {
+            (text_input.text.as_str(), self.style.text, self.scroll)
+        };
+        let cursor = text_input.cursor - self.scroll;
+        let mut characters =
+            characters
+                .chars()
+                .skip(scroll)
+                .map(|character| match text_input.mask {
+                    Some(mask) if !text_input.text.is_empty() => mask,
+                    _ => character,
+                });
+        for i in 0..width {
+            let character = characters.next();
+            let style = if i == cursor {
+                self.style.cursor.coalesce(style)
+            } else if character.is_some() {
+                style
+            } else {
+                break;
+            };
+            let view_cell = ViewCell::new()
+                .with_character(character.unwrap_or(' '))
+                .with_style(style);
+            frame.set_cell_relative(Coord::new(i as i32, 0), 0, view_cell, context);
+        }
+    }
+
+    fn size<C: ColModify>(&mut self, text_input: &'a TextInput, context: ViewContext<C>) -> Size {
+        let _ = text_input;
+        Size::new(context.size.width(), 1)
+    }
+}
+
+/// Edits a `TextInput` until the user presses return, yielding the entered
+/// text, or escape, yielding `None`
+pub struct TextInputRoutine;
+
+impl EventRoutine for TextInputRoutine {
+    type Return = Option<String>;
+    type Data = TextInput;
+    type View = TextInputView;
+    type Event = Input;
+
+    fn handle<EP>(
+        self,
+        data: &mut Self::Data,
+        _view: &Self::View,
+        event_or_peek: EP,
+    ) -> Handled<Self::Return, Self>
+    where
+        EP: EventOrPeek<Event = Self::Event>,
+    {
+        event_or_peek_with_handled(event_or_peek, self, |s, input| {
+            match data.handle_input(input) {
+                TextInputEvent::Submit => Handled::Return(Some(data.text.clone())),
+                TextInputEvent::Cancel => Handled::Return(None),
+                TextInputEvent::Edited | TextInputEvent::Ignored => Handled::Continue(s),
+            }
+        })
+    }
+
+    fn view<F, C>(
+        &self,
+        data: &Self::Data,
+        view: &mut Self::View,
+        context: ViewContext<C>,
+        frame: &mut F,
+    ) where
+        F: Frame,
+        C: ColModify,
+    {
+        view.view(data, context, frame)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn scroll_and_mask() {
+        let mut test_grid = chargrid_test_grid::TestGrid::new(Size::new(4, 1));
+        let context = ViewContext::default_with_size(Size::new(4, 1));
+        let mut text_input = TextInput::new().with_max_length(6);
+        for character in "abcdefgh".chars() {
+            text_input.handle_input(Input::Keyboard(KeyboardInput::Char(character)));
+        }
+        assert_eq!(text_input.text(), "abcdef");
+        let mut view = TextInputView::default();
+        view.view(&text_input, context, &mut test_grid);
+        assert_eq!(test_grid.string_rows(), &["def "]);
+        text_input.move_home();
+        text_input.delete();
+        let mut test_grid = chargrid_test_grid::TestGrid::new(Size::new(4, 1));
+        view.view(&text_input, context, &mut test_grid);
+        assert_eq!(test_grid.string_rows(), &["bcde"]);
+        let text_input = text_input.with_mask('*');
+        view.view(&text_input, context, &mut test_grid);
+        assert_eq!(test_grid.string_rows(), &["****"]);
+    }
+}
@@ -0,0 +1,155 @@
+use crate::align::{AlignBuffer, AlignedWrap, TextAlignment};
+use crate::expand::{CharacterExpansion, ExpandedWrap};
+use crate::wrap;
+use chargrid_render::*;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+const ELLIPSIS: char = '…';
+
+/// How single-line text views display text which is wider than the view
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Truncation {
+    /// Cut the text off at the edge of the view
+    Clip,
+    /// Replace the end of the text with an ellipsis
+    EndEllipsis,
+    /// Replace the start of the text with an ellipsis
+    StartEllipsis,
+    /// Replace the middle of the text with an ellipsis, keeping the start and
+    /// end visible (e.g. for file paths)
+    MiddleElision,
+    /// Fade the colours of the last `cells` visible cells towards `to`
+    FadeOut { cells: u32, to: Rgb24 },
+}
+
+impl Default for Truncation {
+    fn default() -> Self {
+        Truncation::Clip
+    }
+}
+
+/// Collects the cells drawn in the first row, in order of column
+struct LineFrame<'a> {
+    line: &'a mut Vec<(char, Style)>,
+}
+
+impl<'a> Frame for LineFrame<'a> {
+    fn set_cell_relative<C: ColModify>(
+        &mut self,
+        relative_coord: Coord,
+        _relative_depth: i8,
+        relative_cell: ViewCell,
+        _context: ViewContext<C>,
+    ) {
+        if relative_coord.y != 0 || relative_coord.x < 0 {
+            return;
+        }
+        if let Some(character) = relative_cell.character {
+            let x = relative_coord.x as usize;
+            if x >= self.line.len() {
+                self.line.resize(x + 1, (' ', relative_cell.style));
+            }
+            self.line[x] = (character, relative_cell.style);
+        }
+    }
+    fn set_cell_absolute(&mut self, _: Coord, _: i8, _: ViewCell) {}
+    fn blend_cell_background_absolute<B: Blend>(&mut self, _: Coord, _: i8, _: Rgb24, _: u8, _: B) {
+    }
+}
+
+/// Lay out the first line of some styled characters as they would be drawn
+/// without truncation, expanding characters and handling carriage returns
+pub(crate) fn single_line<I>(characters: I, expansion: &CharacterExpansion) -> Vec<(char, Style)>
+where
+    I: IntoIterator<Item = (char, Style)>,
+{
+    let mut none = wrap::None::new();
+    let mut align_buffer = AlignBuffer::default();
+    let mut wrap = ExpandedWrap {
+        wrap: AlignedWrap {
+            wrap: &mut none,
+            alignment: TextAlignment::Left,
+            buffer: &mut align_buffer,
+        },
+        expansion,
+        column: 0,
+    };
+    let context = ViewContext::default_with_size(Size::new(Size::max_field(), 1));
+    let mut line = Vec::new();
+    let mut frame = LineFrame { line: &mut line };
+    wrap.clear();
+    for (character, style) in characters
+        .into_iter()
+        .take_while(|&(character, _)| character != '\n')
+    {
+        wrap.process_character(character, style, context, &mut frame);
+    }
+    wrap.flush(context, &mut frame);
+    line
+}
+
+/// Draw a single line of styled characters, truncating them to the width of
+/// the context. An ellipsis takes the style of the first character it
+/// replaces.
+pub(crate) fn view_truncated<F: Frame, C: ColModify>(
+    characters: &[(char, Style)],
+    truncation: Truncation,
+    context: ViewContext<C>,
+    frame: &mut F,
+) {
+    let width = context.size.width() as usize;
+    let mut draw = |x: usize, (character, style): (char, Style)| {
+        let view_cell = ViewCell::new().with_character(character).with_style(style);
+        frame.set_cell_relative(Coord::new(x as i32, 0), 0, view_cell, context);
+    };
+    if characters.len() <= width {
+        for (x, &cell) in characters.iter().enumerate() {
+            draw(x, cell);
+        }
+        return;
+    }
+    if width == 0 {
+        return;
+    }
+    if let Truncation::FadeOut { cells, to } = truncation {
+        let fade_start = width - (cells as usize).min(width);
+        for (x, &(character, style)) in characters[..width].iter().enumerate() {
+            if x < fade_start {
+                draw(x, (character, style));
+            } else {
+                let by = ((x + 1 - fade_start) * 255 / (width + 1 - fade_start)) as u8;
+                let fade =
+                    |rgb24: Option<Rgb24>| rgb24.map(|rgb24| rgb24.linear_interpolate(to, by));
+                let style = Style {
+                    foreground: fade(style.foreground),
+                    background: fade(style.background),
+                    ..style
+                };
+                draw(x, (character, style));
+            }
+        }
+        return;
+    }
+    let ellipsis = |(_, style): (char, Style)| (ELLIPSIS, style);
+    let (head, tail) = match truncation {
+        Truncation::Clip | Truncation::FadeOut { .. } => (width, 0),
+        Truncation::EndEllipsis => (width - 1, 0),
+        Truncation::StartEllipsis => (0, width - 1),
+        Truncation::MiddleElision => (width / 2, (width - 1) / 2),
+    };
+    let mut x = 0;
+    for &cell in &characters[..head] {
+        draw(x, cell);
+        x += 1;
+    }
+    if head + tail < width {
+        draw(x, ellipsis(characters[head]));
+        x += 1;
+    }
+    for &cell in &characters[(characters.len() - tail)..] {
+        draw(x, cell);
+        x += 1;
+    }
+}
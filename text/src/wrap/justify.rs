@@ -0,0 +1,62 @@
+use super::layout::{Breaking, Hyphenation, Layout};
+use super::Wrap;
+use chargrid_render::*;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+/// Wraps text at spaces, and then widens the spaces on each wrapped line so
+/// that the line fills the available width. Lines ending in a newline, and the
+/// final line, are left-aligned.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Justify {
+    layout: Layout,
+}
+
+impl Justify {
+    pub fn new() -> Self {
+        Self {
+            layout: Layout::new(Breaking::Spaces, true),
+        }
+    }
+
+    pub fn with_hyphenation(mut self, hyphenation: Hyphenation) -> Self {
+        self.layout.set_hyphenation(Some(hyphenation));
+        self
+    }
+}
+
+impl Default for Justify {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Wrap for Justify {
+    fn clear(&mut self) {
+        self.layout.clear();
+    }
+
+    fn process_character<F: Frame, C: ColModify>(
+        &mut self,
+        character: char,
+        style: Style,
+        context: ViewContext<C>,
+        frame: &mut F,
+    ) {
+        self.layout
+            .process_character(character, style, context, frame);
+    }
+
+    fn flush<F: Frame, C: ColModify>(&mut self, context: ViewContext<C>, frame: &mut F) {
+        self.layout.flush(context, frame);
+    }
+
+    fn num_lines(&self) -> usize {
+        self.layout.num_lines()
+    }
+
+    fn next_column(&self, width: u32) -> Option<u32> {
+        Some(self.layout.next_column(width))
+    }
+}
@@ -0,0 +1,354 @@
+use chargrid_render::*;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use xi_unicode::LineBreakLeafIter;
+
+/// Settings for breaking words across lines without consulting a
+/// dictionary. A word may be broken at any character, so long as enough
+/// characters remain on either side of the break.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy)]
+pub struct Hyphenation {
+    /// Drawn at the end of a line after the first part of a broken word
+    pub hyphen: char,
+    /// Words shorter than this are moved to the next line rather than broken,
+    /// unless they are too long to fit on a line by themselves
+    pub min_word_len: usize,
+    /// The minimum number of characters left before the hyphen
+    pub min_prefix: usize,
+    /// The minimum number of characters moved to the next line
+    pub min_suffix: usize,
+}
+
+impl Hyphenation {
+    pub fn new(hyphen: char) -> Self {
+        Self {
+            hyphen,
+            min_word_len: 6,
+            min_prefix: 2,
+            min_suffix: 2,
+        }
+    }
+
+    /// The number of characters of a word of length `word_len` to place
+    /// before the hyphen, given `available` cells remaining on the line
+    fn prefix_len(&self, word_len: usize, available: usize, word_fits_line: bool) -> Option<usize> {
+        if word_fits_line && word_len < self.min_word_len {
+            return None;
+        }
+        let prefix_len = available
+            .checked_sub(1)?
+            .min(word_len.checked_sub(self.min_suffix)?);
+        if prefix_len >= self.min_prefix.max(1) {
+            Some(prefix_len)
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for Hyphenation {
+    fn default() -> Self {
+        Self::new('-')
+    }
+}
+
+/// How to decide where lines may be broken
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Breaking {
+    /// Lines may be broken after any sequence of spaces
+    Spaces,
+    /// Lines may be broken wherever the unicode line breaking algorithm
+    /// (UAX #14) allows
+    UnicodeLineBreak,
+}
+
+#[derive(Clone, Default)]
+struct LineBreakState(Option<LineBreakLeafIter>);
+
+impl fmt::Debug for LineBreakState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("LineBreakState")
+    }
+}
+
+impl LineBreakState {
+    fn clear(&mut self) {
+        self.0 = None;
+    }
+
+    /// Feeds the next character to the line breaking algorithm, returning
+    /// true if a line may be broken immediately before it
+    fn is_break_before(&mut self, character: char) -> bool {
+        let mut buf = [0; 4];
+        let s: &str = character.encode_utf8(&mut buf);
+        match self.0.as_mut() {
+            None => {
+                let mut iter = LineBreakLeafIter::new(s, 0);
+                iter.next(s);
+                self.0 = Some(iter);
+                false
+            }
+            Some(iter) => {
+                let mut is_break = false;
+                while iter.next(s).0 != s.len() {
+                    is_break = true;
+                }
+                is_break
+            }
+        }
+    }
+}
+
+/// The number of columns to add to each of `num_gaps` gaps between words to
+/// widen a line by `extra` columns, with earlier gaps taking any remainder.
+/// Shared by the `Justify` wrap and `TextAlignment::Justify` so that both
+/// justify lines in the same way.
+pub(crate) fn justified_gap_widths(extra: usize, num_gaps: usize) -> impl Iterator<Item = usize> {
+    (0..num_gaps).map(move |i| extra / num_gaps + if i < extra % num_gaps { 1 } else { 0 })
+}
+
+/// A word followed by the spaces after it. Lines are only broken between
+/// segments (unless a single segment is too long to fit on a line).
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default)]
+struct Segment {
+    word: Vec<ViewCell>,
+    space: Vec<ViewCell>,
+}
+
+impl Segment {
+    fn is_empty(&self) -> bool {
+        self.word.is_empty() && self.space.is_empty()
+    }
+}
+
+/// Buffers a line of text at a time so the position of each word is known
+/// before any of the line is drawn. Shared by the wrap strategies which need
+/// to look ahead.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub(crate) struct Layout {
+    breaking: Breaking,
+    justify: bool,
+    hyphenation: Option<Hyphenation>,
+    y: i32,
+    line: Vec<Segment>,
+    line_width: usize,
+    line_is_continuation: bool,
+    pending: Segment,
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    line_break_state: LineBreakState,
+}
+
+impl Layout {
+    pub(crate) fn new(breaking: Breaking, justify: bool) -> Self {
+        Self {
+            breaking,
+            justify,
+            hyphenation: None,
+            y: 0,
+            line: Vec::new(),
+            line_width: 0,
+            line_is_continuation: false,
+            pending: Segment::default(),
+            line_break_state: LineBreakState::default(),
+        }
+    }
+
+    pub(crate) fn set_hyphenation(&mut self, hyphenation: Option<Hyphenation>) {
+        self.hyphenation = hyphenation;
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.y = 0;
+        self.line.clear();
+        self.line_width = 0;
+        self.line_is_continuation = false;
+        self.pending = Segment::default();
+        self.line_break_state.clear();
+    }
+
+    pub(crate) fn num_lines(&self) -> usize {
+        self.y as usize + 1
+    }
+
+    /// The column after the pending segment, assuming it moves to the next
+    /// line without being broken if it doesn't fit on this one
+    pub(crate) fn next_column(&self, width: u32) -> u32 {
+        let width = width as usize;
+        let word_len = self.pending.word.len();
+        let column = if self.line_width + word_len <= width {
+            self.line_width + word_len
+        } else if word_len <= width {
+            word_len
+        } else {
+            word_len % width.max(1)
+        };
+        (column + self.pending.space.len()) as u32
+    }
+
+    pub(crate) fn process_character<F: Frame, C: ColModify>(
+        &mut self,
+        character: char,
+        style: Style,
+        context: ViewContext<C>,
+        frame: &mut F,
+    ) {
+        if context.size.width() == 0 {
+            return;
+        }
+        match character {
+            '\n' => {
+                self.end_line(context, frame);
+                self.y += 1;
+            }
+            '\r' => self.end_line(context, frame),
+            other => {
+                let is_break = match self.breaking {
+                    Breaking::Spaces => other != ' ' && !self.pending.space.is_empty(),
+                    Breaking::UnicodeLineBreak => self.line_break_state.is_break_before(other),
+                };
+                if is_break {
+                    self.finish_segment(context, frame);
+                }
+                let view_cell = ViewCell {
+                    character: Some(other),
+                    style,
+                };
+                if other == ' ' {
+                    self.pending.space.push(view_cell);
+                } else {
+                    let Segment { word, space } = &mut self.pending;
+                    word.append(space);
+                    word.push(view_cell);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn flush<F: Frame, C: ColModify>(&mut self, context: ViewContext<C>, frame: &mut F) {
+        if context.size.width() == 0 {
+            self.clear();
+            return;
+        }
+        self.finish_segment(context, frame);
+        self.draw_line(false, context, frame);
+    }
+
+    fn end_line<F: Frame, C: ColModify>(&mut self, context: ViewContext<C>, frame: &mut F) {
+        self.finish_segment(context, frame);
+        self.draw_line(false, context, frame);
+        self.line_is_continuation = false;
+        self.line_break_state.clear();
+    }
+
+    fn wrap_line<F: Frame, C: ColModify>(&mut self, context: ViewContext<C>, frame: &mut F) {
+        self.draw_line(true, context, frame);
+        self.y += 1;
+        self.line_is_continuation = true;
+    }
+
+    fn finish_segment<F: Frame, C: ColModify>(&mut self, context: ViewContext<C>, frame: &mut F) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let mut segment = std::mem::take(&mut self.pending);
+        let width = context.size.width() as usize;
+        loop {
+            if self.line.is_empty() && self.line_is_continuation && segment.word.is_empty() {
+                // spaces at the start of a wrapped line are dropped
+                return;
+            }
+            let word_len = segment.word.len();
+            if self.line_width + word_len <= width {
+                self.line_width += word_len + segment.space.len();
+                self.line.push(segment);
+                return;
+            }
+            let available = width.saturating_sub(self.line_width);
+            let hyphenated_prefix_len = self.hyphenation.and_then(|hyphenation| {
+                hyphenation
+                    .prefix_len(word_len, available, word_len <= width)
+                    .map(|prefix_len| (prefix_len, hyphenation.hyphen))
+            });
+            let split_at = if let Some((prefix_len, hyphen)) = hyphenated_prefix_len {
+                let mut prefix = segment.word[0..prefix_len].to_vec();
+                let hyphen_cell = ViewCell {
+                    character: Some(hyphen),
+                    ..prefix[prefix_len - 1]
+                };
+                prefix.push(hyphen_cell);
+                Some((prefix_len, prefix))
+            } else if self.line.is_empty() {
+                Some((width, segment.word[0..width].to_vec()))
+            } else {
+                None
+            };
+            if let Some((split_at, prefix)) = split_at {
+                segment.word.drain(0..split_at);
+                self.line_width += prefix.len();
+                self.line.push(Segment {
+                    word: prefix,
+                    space: Vec::new(),
+                });
+            }
+            self.wrap_line(context, frame);
+        }
+    }
+
+    fn draw_line<F: Frame, C: ColModify>(
+        &mut self,
+        justify: bool,
+        context: ViewContext<C>,
+        frame: &mut F,
+    ) {
+        let width = context.size.width() as usize;
+        let trailing_space = self.line.last().map(|s| s.space.len()).unwrap_or(0);
+        let content_width = self.line_width - trailing_space;
+        let last_index = self.line.len().saturating_sub(1);
+        // spaces before the first word are indentation rather than a gap
+        let first_word_index = self
+            .line
+            .iter()
+            .position(|segment| !segment.word.is_empty())
+            .unwrap_or(last_index);
+        let is_gap = |i: usize, segment: &Segment| {
+            i >= first_word_index && i < last_index && !segment.space.is_empty()
+        };
+        let num_gaps = if justify && self.justify {
+            self.line
+                .iter()
+                .enumerate()
+                .filter(|&(i, segment)| is_gap(i, segment))
+                .count()
+        } else {
+            0
+        };
+        let mut gap_widths = justified_gap_widths(width.saturating_sub(content_width), num_gaps);
+        let mut x = 0;
+        for (i, segment) in self.line.drain(..).enumerate() {
+            let num_extra = if is_gap(i, &segment) {
+                gap_widths.next().unwrap_or(0)
+            } else {
+                0
+            };
+            for view_cell in segment.word {
+                frame.set_cell_relative(Coord::new(x, self.y), 0, view_cell, context);
+                x += 1;
+            }
+            let last_space = segment.space.last().cloned();
+            let extra_spaces = last_space.into_iter().cycle().take(num_extra);
+            for view_cell in segment.space.into_iter().chain(extra_spaces) {
+                if x as usize >= width {
+                    break;
+                }
+                frame.set_cell_relative(Coord::new(x, self.y), 0, view_cell, context);
+                x += 1;
+            }
+        }
+        self.line_width = 0;
+    }
+}
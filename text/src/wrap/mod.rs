@@ -2,10 +2,26 @@ use chargrid_render::*;
 #[cfg(feature = "serialize")]
 use serde::{Deserialize, Serialize};
 
-pub trait Wrap: private_wrap::Sealed {
-    #[doc(hidden)]
+mod justify;
+mod layout;
+mod line_break;
+
+pub use justify::*;
+pub(crate) use layout::justified_gap_widths;
+pub use layout::Hyphenation;
+pub use line_break::*;
+
+/// A strategy for laying out a stream of characters in the space available
+/// to a text view. Views call `clear` before rendering, then
+/// `process_character` once for each character of the text, and finally
+/// `flush`.
+pub trait Wrap {
+    /// Reset any state left over from rendering previous text
     fn clear(&mut self);
-    #[doc(hidden)]
+    /// Handle the next character of the text, drawing any cells which are
+    /// ready to be drawn. Implementations may buffer characters until they
+    /// know where they belong, so long as they draw them by the time `flush`
+    /// returns.
     fn process_character<F: Frame, C: ColModify>(
         &mut self,
         character: char,
@@ -13,13 +29,22 @@ pub trait Wrap: private_wrap::Sealed {
         context: ViewContext<C>,
         frame: &mut F,
     );
-    #[doc(hidden)]
+    /// Draw any buffered characters. Called once after all characters have
+    /// been processed.
     fn flush<F: Frame, C: ColModify>(&mut self, context: ViewContext<C>, frame: &mut F) {
         let _ = context;
         let _ = frame;
     }
-    #[doc(hidden)]
+    /// The number of lines of text laid out so far
     fn num_lines(&self) -> usize;
+    /// The column a space would be placed in if it were the next character,
+    /// given the width available, or `None` if this isn't known until later
+    /// characters are processed. Text views use this to expand tabs relative
+    /// to where they appear after wrapping.
+    fn next_column(&self, width: u32) -> Option<u32> {
+        let _ = width;
+        Option::None
+    }
 }
 
 #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
@@ -114,6 +139,10 @@ impl Wrap for None {
     fn num_lines(&self) -> usize {
         self.cursor.y as usize + 1
     }
+
+    fn next_column(&self, _width: u32) -> Option<u32> {
+        Some(self.cursor.x as u32)
+    }
 }
 
 impl Wrap for Word {
@@ -201,6 +230,11 @@ impl Wrap for Word {
     fn num_lines(&self) -> usize {
         self.cursor.y as usize + 1
     }
+
+    fn next_column(&self, width: u32) -> Option<u32> {
+        let column = self.cursor.x as u32 + self.current_word_buffer.len() as u32;
+        Some(if column >= width { 0 } else { column })
+    }
 }
 
 impl Wrap for Char {
@@ -239,11 +273,7 @@ impl Wrap for Char {
     fn num_lines(&self) -> usize {
         self.cursor.y as usize + 1
     }
-}
-
-mod private_wrap {
-    pub trait Sealed {}
-    impl Sealed for super::None {}
-    impl Sealed for super::Word {}
-    impl Sealed for super::Char {}
+    fn next_column(&self, _width: u32) -> Option<u32> {
+        Some(self.cursor.x as u32)
+    }
 }